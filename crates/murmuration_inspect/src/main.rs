@@ -0,0 +1,316 @@
+//! A command line tool for inspecting and querying saved murmuration octrees and point clouds.
+//!
+//! Run `murmuration-inspect --help` for usage.
+
+use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use murmuration_octree::{LoadError, Octree, OrderedBinary, PointFormat};
+
+const USAGE: &str = "\
+Usage: murmuration-inspect <FILE> [OPTIONS] [COMMAND]...
+
+Loads a tree saved with `Octree::to_bytes` or a point cloud (.ply, .xyz, .txt or .csv) and runs
+each command in turn. With no commands it runs `stats validate`.
+
+Commands:
+    stats                  Print the shape and memory use of the tree
+    validate               Check the tree's invariants
+    get X Y Z              List the items at a point
+    within X Y Z RADIUS    List the items within RADIUS of a point
+    nearest X Y Z [K]      List the K (default 1) items nearest to a point
+
+Options:
+    --format FORMAT        Read FILE as `tree`, `ply`, `xyz` or `csv` rather than guessing from its
+                           extension
+    --bucket-size N        Store point clouds with buckets of up to N items
+    --repeat N             Run each query N times and print the average time (default 1)
+    --limit N              Print at most N items from each query (default 20)
+    -h, --help             Print this help
+
+Saved trees can be loaded if their points are `[N; 3]` where N is f32, f64, i16, i32, i64, u8,
+u16, u32, u64 or u128, and their data is (), usize, i8 or one of those numbers. Point clouds are
+loaded with `[f32; 3]` points and the index of each point as its data.
+";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        print!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    match Options::parse(args).and_then(|options| options.run()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+enum Source {
+    Tree,
+    Points(PointFormat),
+}
+
+enum Command {
+    Stats,
+    Validate,
+    Get([String; 3]),
+    Within([String; 3], String),
+    Nearest([String; 3], usize),
+}
+
+struct Options {
+    path: String,
+    source: Source,
+    bucket_size: usize,
+    repeat: u32,
+    limit: usize,
+    commands: Vec<Command>,
+}
+
+impl Options {
+    fn parse(args: Vec<String>) -> Result<Self, String> {
+        let mut path = None;
+        let mut source = None;
+        let mut bucket_size = 1;
+        let mut repeat = 1;
+        let mut limit = 20;
+        let mut commands = Vec::new();
+
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" => source = Some(parse_source(&next_value(&mut args, "--format")?)?),
+                "--bucket-size" => {
+                    bucket_size = parse_number(&next_value(&mut args, "--bucket-size")?)?;
+                }
+                "--repeat" => {
+                    repeat = parse_number::<u32>(&next_value(&mut args, "--repeat")?)?.max(1);
+                }
+                "--limit" => limit = parse_number(&next_value(&mut args, "--limit")?)?,
+                "stats" => commands.push(Command::Stats),
+                "validate" => commands.push(Command::Validate),
+                "get" => commands.push(Command::Get(take_point(&mut args)?)),
+                "within" => {
+                    let point = take_point(&mut args)?;
+                    commands.push(Command::Within(point, next_value(&mut args, "within")?));
+                }
+                "nearest" => {
+                    let point = take_point(&mut args)?;
+                    let count = match args.peek().map(|arg| arg.parse()) {
+                        Some(Ok(count)) => {
+                            args.next();
+                            count
+                        }
+                        _ => 1,
+                    };
+                    commands.push(Command::Nearest(point, count));
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown option `{arg}`")),
+                _ if path.is_none() => path = Some(arg),
+                _ => return Err(format!("unknown command `{arg}`")),
+            }
+        }
+
+        let path = path.ok_or("no file given")?;
+        let source = match source {
+            Some(source) => source,
+            None => guess_source(&path),
+        };
+        if commands.is_empty() {
+            commands = vec![Command::Stats, Command::Validate];
+        }
+        Ok(Self {
+            path,
+            source,
+            bucket_size,
+            repeat,
+            limit,
+            commands,
+        })
+    }
+
+    /// Loads the file and runs the commands, returning `false` if validation failed
+    fn run(&self) -> Result<bool, String> {
+        let start = Instant::now();
+        match self.source {
+            Source::Points(format) => {
+                let file = fs::File::open(&self.path).map_err(|err| err.to_string())?;
+                // The bucket size has to be set first, as it only affects leaves created after it
+                let mut tree = Octree::with_bucket_size(self.bucket_size);
+                tree.add_points_with(file, format, |index, _| index)
+                    .map_err(|err| err.to_string())?;
+                self.inspect(&tree, start.elapsed())
+            }
+            Source::Tree => {
+                let bytes = fs::read(&self.path).map_err(|err| err.to_string())?;
+                macro_rules! try_points {
+                    ($($n:ty),*) => {$(
+                        if let Some(valid) = self.load_tree::<$n>(&bytes, start)? {
+                            return Ok(valid);
+                        }
+                    )*};
+                }
+                try_points!(f32, f64, i32, i64, u32, u64, i16, u16, u8, u128);
+                Err(format!(
+                    "`{}` was saved with an unsupported point type",
+                    self.path
+                ))
+            }
+        }
+    }
+
+    /// Loads a saved tree with the point type `[N; 3]`, returning `None` if it has a different
+    /// point type
+    fn load_tree<N>(&self, bytes: &[u8], start: Instant) -> Result<Option<bool>, String>
+    where
+        N: OrderedBinary + FromStr + Debug,
+    {
+        // Trees with any other data type can't be named here, so they aren't supported
+        macro_rules! try_data {
+            ($($d:ty),*) => {$(
+                match Octree::<$d, [N; 3]>::from_bytes(bytes) {
+                    Ok(tree) => return self.inspect(&tree, start.elapsed()).map(Some),
+                    Err(LoadError::WrongDataType) => {}
+                    Err(LoadError::WrongPointType) => return Ok(None),
+                    Err(err) => return Err(err.to_string()),
+                }
+            )*};
+        }
+        try_data!(
+            usize,
+            u64,
+            u32,
+            u16,
+            u8,
+            u128,
+            i64,
+            i32,
+            i16,
+            i8,
+            f64,
+            f32,
+            ()
+        );
+        Err(format!(
+            "`{}` was saved with an unsupported data type",
+            self.path
+        ))
+    }
+
+    fn inspect<D, N>(&self, tree: &Octree<D, [N; 3]>, load_time: Duration) -> Result<bool, String>
+    where
+        D: Debug,
+        N: OrderedBinary + FromStr + Debug,
+    {
+        println!(
+            "Loaded {} items from `{}` in {load_time:?}",
+            tree.len(),
+            self.path
+        );
+        let mut valid = true;
+        for command in &self.commands {
+            println!();
+            match command {
+                Command::Stats => println!("{}", tree.stats()),
+                Command::Validate => {
+                    let start = Instant::now();
+                    let result = tree.validate();
+                    let elapsed = start.elapsed();
+                    match result {
+                        Ok(()) => println!("Tree is valid (checked in {elapsed:?})"),
+                        Err(err) => {
+                            println!("Tree is INVALID: {err}");
+                            valid = false;
+                        }
+                    }
+                }
+                Command::Get(point) => {
+                    let point = parse_point::<N>(point)?;
+                    println!("get {point:?}");
+                    self.query(|| tree.get(&point).collect());
+                }
+                Command::Within(point, radius) => {
+                    let point = parse_point::<N>(point)?;
+                    let radius = parse_number::<N>(radius)?;
+                    println!("within {point:?} {radius:?}");
+                    self.query(|| tree.within(&point, radius.clone()).collect());
+                }
+                Command::Nearest(point, count) => {
+                    let point = parse_point::<N>(point)?;
+                    println!("nearest {point:?} {count}");
+                    self.query(|| tree.nearest_iter(&point).take(*count).collect());
+                }
+            }
+        }
+        Ok(valid)
+    }
+
+    /// Runs `query` `repeat` times, then prints its results and the average time it took
+    fn query<D: Debug>(&self, mut query: impl FnMut() -> Vec<D>) {
+        let start = Instant::now();
+        let mut results = query();
+        for _ in 1..self.repeat {
+            results = query();
+        }
+        let elapsed = start.elapsed() / self.repeat;
+        println!("{} results in {elapsed:?}", results.len());
+        for result in results.iter().take(self.limit) {
+            println!("    {result:?}");
+        }
+        if results.len() > self.limit {
+            println!("    ... ({} more)", results.len() - self.limit);
+        }
+    }
+}
+
+fn guess_source(path: &str) -> Source {
+    let extension = Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("ply") => Source::Points(PointFormat::Ply),
+        Some("xyz" | "txt") => Source::Points(PointFormat::Xyz),
+        Some("csv") => Source::Points(PointFormat::Csv),
+        _ => Source::Tree,
+    }
+}
+
+fn parse_source(format: &str) -> Result<Source, String> {
+    match format {
+        "tree" => Ok(Source::Tree),
+        "ply" => Ok(Source::Points(PointFormat::Ply)),
+        "xyz" => Ok(Source::Points(PointFormat::Xyz)),
+        "csv" => Ok(Source::Points(PointFormat::Csv)),
+        _ => Err(format!("unknown format `{format}`")),
+    }
+}
+
+fn next_value(args: &mut impl Iterator<Item = String>, name: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("`{name}` needs a value"))
+}
+
+fn take_point(args: &mut impl Iterator<Item = String>) -> Result<[String; 3], String> {
+    Ok([
+        next_value(args, "X")?,
+        next_value(args, "Y")?,
+        next_value(args, "Z")?,
+    ])
+}
+
+fn parse_point<N: FromStr>(point: &[String; 3]) -> Result<[N; 3], String> {
+    let [x, y, z] = point;
+    Ok([parse_number(x)?, parse_number(y)?, parse_number(z)?])
+}
+
+fn parse_number<N: FromStr>(text: &str) -> Result<N, String> {
+    text.parse()
+        .map_err(|_| format!("`{text}` isn't a valid number here"))
+}
@@ -0,0 +1,78 @@
+//! Runs the command line tool on small point clouds and saved trees.
+use murmuration_octree::Octree;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Writes `contents` to a file in the temporary directory that is unique to this test run
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("murmuration-inspect-{}-{name}", std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Runs the tool with `args` and returns what it printed, panicking if it failed
+fn inspect(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_murmuration-inspect"))
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        output.status.success(),
+        "{stdout}{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout
+}
+
+/// Returns the value of a line of `stats` output, such as `buckets`
+fn stat(output: &str, name: &str) -> usize {
+    let line = output
+        .lines()
+        .find(|line| line.starts_with(&format!("{name}:")))
+        .unwrap_or_else(|| panic!("no `{name}` in {output}"));
+    line.split_whitespace().last().unwrap().parse().unwrap()
+}
+
+#[test]
+fn bucket_size_changes_stats() {
+    let mut cloud = String::new();
+    for x in 0..10 {
+        for y in 0..10 {
+            cloud += &format!("{x} {y} 0\n");
+        }
+    }
+    let path = temp_file("buckets.xyz", cloud.as_bytes());
+    let path = path.to_str().unwrap();
+
+    let plain = inspect(&[path, "stats", "validate"]);
+    assert_eq!(stat(&plain, "items"), 100);
+    assert_eq!(stat(&plain, "leaves"), 100);
+    assert_eq!(stat(&plain, "buckets"), 0);
+
+    let bucketed = inspect(&[path, "--bucket-size", "16", "stats", "validate"]);
+    assert_eq!(stat(&bucketed, "items"), 100);
+    assert_eq!(stat(&bucketed, "leaves"), 0);
+    assert!(stat(&bucketed, "buckets") > 0);
+    assert!(stat(&bucketed, "bytes used") < stat(&plain, "bytes used"));
+    assert!(bucketed.contains("Tree is valid"));
+}
+
+#[test]
+fn loads_saved_trees() {
+    let mut bytes = Octree::<usize, [u8; 3]>::new();
+    bytes.add(&[1, 2, 3], 7);
+    bytes.add(&[200, 0, 255], 8);
+    let path = temp_file("u8.tree", &bytes.to_bytes());
+    let output = inspect(&[path.to_str().unwrap(), "get", "1", "2", "3"]);
+    assert!(output.contains("Loaded 2 items"), "{output}");
+    assert!(output.contains("1 results"), "{output}");
+
+    let mut wide = Octree::<f32, [u128; 3]>::new();
+    wide.add(&[u128::MAX, 0, 1], 0.5);
+    let path = temp_file("u128.tree", &wide.to_bytes());
+    let output = inspect(&[path.to_str().unwrap(), "validate"]);
+    assert!(output.contains("Loaded 1 items"), "{output}");
+    assert!(output.contains("Tree is valid"), "{output}");
+}
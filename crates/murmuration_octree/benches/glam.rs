@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use glam::{UVec3, Vec3};
+use glam::{DVec3, UVec3, Vec3};
 use murmuration_octree::Octree;
 use rand::distributions::{Distribution, Uniform};
 use rand::prelude::SliceRandom;
@@ -90,6 +90,9 @@ fn remove_many(c: &mut Criterion) {
     });
 }
 
+/// The child pruning in `within` can use SIMD, to compare it against the scalar version run
+/// `cargo bench --features glam -- within --save-baseline scalar` and then
+/// `cargo bench --features glam,simd -- within --baseline scalar`.
 fn within_many(c: &mut Criterion) {
     let mut tree = Octree::new();
     // Has to be something like 30000 otherwise it is too easy to overflow
@@ -166,6 +169,49 @@ fn within_many(c: &mut Criterion) {
             );
         })
     });
+    c.bench_function("within_100000_f32", |b| {
+        b.iter(|| {
+            black_box(
+                tree.within(
+                    &Vec3::new(
+                        uniform.sample(&mut rng),
+                        uniform.sample(&mut rng),
+                        uniform.sample(&mut rng),
+                    ),
+                    100_000.0,
+                )
+                .count(),
+            );
+        })
+    });
+
+    let mut tree = Octree::new();
+    for i in 0..100_000 {
+        tree.add(
+            &DVec3::new(
+                uniform.sample(&mut rng).into(),
+                uniform.sample(&mut rng).into(),
+                uniform.sample(&mut rng).into(),
+            ),
+            NonZeroU64::new(i + 1).unwrap(),
+        )
+    }
+
+    c.bench_function("within_1000_f64", |b| {
+        b.iter(|| {
+            black_box(
+                tree.within(
+                    &DVec3::new(
+                        uniform.sample(&mut rng).into(),
+                        uniform.sample(&mut rng).into(),
+                        uniform.sample(&mut rng).into(),
+                    ),
+                    1000.0,
+                )
+                .count(),
+            );
+        })
+    });
 }
 
 fn add_many_spatialtree(c: &mut Criterion) {
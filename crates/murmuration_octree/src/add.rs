@@ -1,21 +1,67 @@
 use super::{
+    aggregate::Aggregate,
+    error::AddError,
     point::{Point, PointData},
-    Branch, BranchKey, Octree,
+    Branch, BranchKey, NonFinitePolicy, Octree,
 };
 
-impl<D, P: Point> Octree<D, P> {
+impl<D, P: Point, A: Aggregate<D, P>> Octree<D, P, A> {
     /// Adds an item to the tree at the given point with `data`.
+    ///
+    /// If the point isn't finite this follows the tree's [`NonFinitePolicy`].
+    ///
+    /// # Panics
+    /// If the tree is [full](Self::set_max_items) or has run out of keys for its branches, see
+    /// [`try_add`](Self::try_add) to handle these instead.
     pub fn add(&mut self, point: &P, data: D) {
-        self.add_int(point.get_point(), data);
+        self.add_internal(point.get_point(), data);
+    }
+
+    /// Like [`add`](Self::add), except this takes the underlying `PointData` for if you have already converted it.
+    ///
+    /// # Panics
+    /// As for [`add`](Self::add).
+    pub fn add_internal(&mut self, point: PointData<P>, data: D) {
+        if let Some(err) = self.no_room(&point) {
+            panic!("{}", err(data));
+        }
+        self.add_int(point, data);
+    }
+
+    /// Adds an item to the tree at the given point with `data`, returning an error instead of adding
+    /// it if the point has a NaN or infinite coordinate (unless the tree is using
+    /// [`NonFinitePolicy::Park`]) or there isn't room for it.
+    ///
+    /// # Errors
+    /// Returns (with the rejected `data`):
+    /// - [`AddError::NonFinite`] if the point isn't finite.
+    /// - [`AddError::Full`] if the tree already holds its [maximum number of items](Self::set_max_items).
+    /// - [`AddError::OutOfKeys`] if the tree has too many branches to add any more.
+    pub fn try_add(&mut self, point: &P, data: D) -> Result<(), AddError<D>> {
+        let point = point.get_point();
+        if self.non_finite != NonFinitePolicy::Park && !point.is_finite() {
+            return Err(AddError::NonFinite(data));
+        }
+        if let Some(err) = self.no_room(&point) {
+            return Err(err(data));
+        }
+        self.add_int(point, data);
+        Ok(())
     }
 
     pub(crate) fn add_int(&mut self, point: PointData<P>, data: D) {
+        if self.non_finite == NonFinitePolicy::Park && !point.is_finite() {
+            self.parked.push((point, data));
+            return;
+        }
+
         if let Some(child_key) = self.root {
-            if let Some(branch) = self.add_to_branch(child_key, data, point, 0) {
+            let item = Self::item_aggregate(&point, &data);
+            if let Some(branch) = self.add_to_branch(child_key, data, point, 0, &item) {
                 self.root = Some(branch);
             }
         } else {
-            let branch = self.add_branch(Branch::new_data(point, data));
+            let branch = self.add_branch(self.new_leaf(point, data));
             self.root = Some(branch);
         }
     }
@@ -27,6 +73,7 @@ impl<D, P: Point> Octree<D, P> {
         data: D,
         point: PointData<P>,
         depth: u8,
+        item: &A,
     ) -> Option<BranchKey> {
         match self.get_branch(branch) {
             Branch::Leaf {
@@ -41,39 +88,69 @@ impl<D, P: Point> Octree<D, P> {
                 } else {
                     let shared = (&point ^ child_point).leading_zeros();
                     let child_point = child_point.clone();
-                    let new = self.add_branch(Branch::new_data(point.clone(), data));
+                    let new = self.add_branch(self.new_leaf(point.clone(), data));
                     Some(self.add_new_split(new, branch, point, &child_point, shared, depth))
                 }
             }
+            Branch::Bucket { items } => {
+                // Items at the same point can't be split up so they all stay in the bucket
+                if items.len() < self.bucket_size || items.iter().all(|(p, _)| p == &point) {
+                    let Branch::Bucket { items } = self.get_branch_mut(branch) else {
+                        unreachable!()
+                    };
+                    items.push((point, data));
+                    None
+                } else {
+                    let Branch::Bucket { items } = self.get_branch_mut(branch) else {
+                        unreachable!()
+                    };
+                    let mut items = std::mem::take(items);
+                    items.push((point, data));
+                    self.remove_branch(branch);
+                    Some(self.add_buckets(items, depth))
+                }
+            }
             Branch::Skip {
                 point: child_point,
                 point_depth,
                 child: branch_child,
+                ..
             } => {
                 let shared = (&point ^ child_point).leading_zeros();
                 if shared >= *point_depth {
                     // They share all their data (up to point depth)
-                    if let Some(new) = self.add_to_branch(*branch_child, data, point, *point_depth)
+                    if let Some(new) =
+                        self.add_to_branch(*branch_child, data, point, *point_depth, item)
                     {
                         self.set_skip_child(branch, new);
                     }
+                    self.include_item(branch, item);
                     None
                 } else {
                     let child_point = child_point.clone();
-                    let new = self.add_branch(Branch::new_data(point.clone(), data));
-                    Some(self.add_new_split(new, branch, point, &child_point, shared, depth))
+                    // The new split fixes `shared + 1` bits so the skip is redundant if that is all it did
+                    let old = if *point_depth == shared + 1 {
+                        let split = *branch_child;
+                        self.remove_branch(branch);
+                        split
+                    } else {
+                        branch
+                    };
+                    let new = self.add_branch(self.new_leaf(point.clone(), data));
+                    Some(self.add_new_split(new, old, point, &child_point, shared, depth))
                 }
             }
             Branch::Split { children, .. } => {
                 let ind = point.nth(depth) as usize;
                 if let Some(child) = children[ind] {
-                    if let Some(new) = self.add_to_branch(child, data, point, depth + 1) {
+                    if let Some(new) = self.add_to_branch(child, data, point, depth + 1, item) {
                         self.set_split_child(branch, ind, new);
                     }
                 } else {
-                    let new = self.add_branch(Branch::new_data(point, data));
+                    let new = self.add_branch(self.new_leaf(point, data));
                     self.set_split_child(branch, ind, new);
                 }
+                self.include_item(branch, item);
                 None
             }
         }
@@ -94,10 +171,16 @@ impl<D, P: Point> Octree<D, P> {
         let mut children = [None, None, None, None, None, None, None, None];
         children[dir1 as usize] = Some(child1);
         children[dir2 as usize] = Some(child2);
+        let len = self.branch_len(child1) + self.branch_len(child2);
+        let aggregate = self
+            .branch_aggregate(child1)
+            .combine(&self.branch_aggregate(child2));
         let split = self.add_branch(Branch::Split {
             children,
-            occupied: (1_u8.wrapping_shl(dir1 as u32)) | (1_u8.wrapping_shl(dir2 as u32)),
+            occupied: (1_u8.wrapping_shl(u32::from(dir1))) | (1_u8.wrapping_shl(u32::from(dir2))),
             depth: shared + 1,
+            len,
+            aggregate,
         });
 
         if shared > depth {
@@ -105,12 +188,75 @@ impl<D, P: Point> Octree<D, P> {
                 point: point1,
                 point_depth: shared,
                 child: split,
+                len,
+            })
+        } else {
+            split
+        }
+    }
+
+    /// Adds a bucket, or a tree of them if there are too many `items` for one, where all the items
+    /// share the first `depth` bits
+    fn add_buckets(&mut self, items: Vec<(PointData<P>, D)>, depth: u8) -> BranchKey {
+        let point = items[0].0.clone();
+        let shared = items
+            .iter()
+            .map(|(p, _)| (p ^ &point).leading_zeros())
+            .min()
+            .unwrap();
+        if items.len() <= self.bucket_size || shared == P::MAX_DEPTH {
+            return self.add_branch(Branch::Bucket { items });
+        }
+
+        let mut groups: [Vec<_>; 8] = Default::default();
+        for item in items {
+            groups[item.0.nth(shared) as usize].push(item);
+        }
+        let mut children = [None; 8];
+        let mut occupied = 0;
+        let mut len = 0;
+        let mut aggregate = A::default();
+        for (i, group) in groups.into_iter().enumerate() {
+            if !group.is_empty() {
+                let child = self.add_buckets(group, shared + 1);
+                len += self.branch_len(child);
+                aggregate = aggregate.combine(&self.branch_aggregate(child));
+                children[i] = Some(child);
+                occupied |= 1 << i;
+            }
+        }
+        let split = self.add_branch(Branch::Split {
+            children,
+            occupied,
+            depth: shared + 1,
+            len,
+            aggregate,
+        });
+
+        if shared > depth {
+            self.add_branch(Branch::Skip {
+                point,
+                point_depth: shared,
+                child: split,
+                len,
             })
         } else {
             split
         }
     }
 
+    /// Adds a new item (with aggregate `item`) to the totals of the given split or skip branch
+    fn include_item(&mut self, branch: BranchKey, item: &A) {
+        match self.get_branch_mut(branch) {
+            Branch::Split { len, aggregate, .. } => {
+                *len += 1;
+                *aggregate = aggregate.combine(item);
+            }
+            Branch::Skip { len, .. } => *len += 1,
+            Branch::Leaf { .. } | Branch::Bucket { .. } => unreachable!(),
+        }
+    }
+
     /// Sets the child of the given branch to 'new' if it is a skip branch (N.B. must be passed a skip branch)
     fn set_skip_child(&mut self, branch: BranchKey, new: BranchKey) {
         let Branch::Skip { child, .. } = self.get_branch_mut(branch) else {
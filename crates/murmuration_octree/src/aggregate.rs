@@ -0,0 +1,180 @@
+use super::{
+    point::{Point, PointData},
+    Branch, BranchKey, Octree,
+};
+
+/// A summary of a group of items (such as their total mass and centre of mass) which an [`Octree`]
+/// keeps up to date for every branch as items are added, removed and moved.
+///
+/// This must be a commutative monoid, so `combine` should be associative and commutative with
+/// `Self::default()` as the identity (combining anything with it leaves it unchanged).
+///
+/// # Example
+/// ```
+/// use murmuration_octree::{Aggregate, Octree};
+///
+/// #[derive(Clone, Default)]
+/// struct Mass {
+///     total: f32,
+///     weighted: [f32; 3],
+/// }
+///
+/// impl Aggregate<f32, [f32; 3]> for Mass {
+///     fn from_item(point: [f32; 3], mass: &f32) -> Self {
+///         Mass {
+///             total: *mass,
+///             weighted: point.map(|n| n * mass),
+///         }
+///     }
+///
+///     fn combine(&self, other: &Self) -> Self {
+///         Mass {
+///             total: self.total + other.total,
+///             weighted: [0, 1, 2].map(|i| self.weighted[i] + other.weighted[i]),
+///         }
+///     }
+/// }
+///
+/// let mut tree: Octree<f32, [f32; 3], Mass> = Octree::default();
+/// tree.add(&[0.0, 0.0, 0.0], 1.0);
+/// tree.add(&[4.0, 0.0, 0.0], 3.0);
+/// let mass = tree.aggregate();
+/// assert_eq!(mass.total, 4.0);
+/// assert_eq!(mass.weighted[0] / mass.total, 3.0);
+/// ```
+pub trait Aggregate<D, P: Point>: Clone + Default {
+    /// Returns the aggregate of a single item.
+    fn from_item(point: [P::Data; 3], data: &D) -> Self;
+    /// Combines two aggregates into the aggregate of all their items.
+    fn combine(&self, other: &Self) -> Self;
+}
+
+impl<D, P: Point> Aggregate<D, P> for () {
+    fn from_item(_point: [P::Data; 3], _data: &D) -> Self {}
+    fn combine(&self, _other: &Self) -> Self {}
+}
+
+impl<D, P: Point, A: Aggregate<D, P>> Octree<D, P, A> {
+    /// Whether there is anything to keep track of (so that `()` costs nothing)
+    pub(crate) const AGGREGATES: bool = std::mem::size_of::<A>() != 0;
+
+    /// Returns the aggregate of every item in the tree (not including [parked](Self::parked) ones).
+    pub fn aggregate(&self) -> A {
+        self.root
+            .map_or_else(A::default, |root| self.branch_aggregate(root))
+    }
+
+    /// Returns the aggregate of a single item.
+    pub(crate) fn item_aggregate(point: &PointData<P>, data: &D) -> A {
+        if Self::AGGREGATES {
+            A::from_item(point.to_array(), data)
+        } else {
+            A::default()
+        }
+    }
+
+    /// Returns the aggregate of every item below the given branch.
+    pub(crate) fn branch_aggregate(&self, branch: BranchKey) -> A {
+        if !Self::AGGREGATES {
+            return A::default();
+        }
+
+        let mut branch = Some(branch);
+        let mut aggregate = A::default();
+        while let Some(key) = branch {
+            match self.get_branch(key) {
+                Branch::Split {
+                    aggregate: split, ..
+                } => return aggregate.combine(split),
+                Branch::Skip { child, .. } => branch = Some(*child),
+                Branch::Leaf { point, data, child } => {
+                    aggregate = aggregate.combine(&Self::item_aggregate(point, data));
+                    branch = *child;
+                }
+                Branch::Bucket { items } => {
+                    return items.iter().fold(aggregate, |acc, (point, data)| {
+                        acc.combine(&Self::item_aggregate(point, data))
+                    });
+                }
+            }
+        }
+        aggregate
+    }
+
+    /// Recalculates the aggregate of every split branch on the way to `point`.
+    pub(crate) fn refresh_aggregates(&mut self, point: &PointData<P>) {
+        if let (true, Some(root)) = (Self::AGGREGATES, self.root) {
+            self.refresh_branch(root, point);
+        }
+    }
+
+    /// Calls `f` on every item in the tree (including [parked](Self::parked) ones) without moving
+    /// them, such as to remap ids after loading a saved tree.
+    pub fn update_data(&mut self, mut f: impl FnMut(&mut D)) {
+        for (_, branch) in &mut self.branches {
+            match branch {
+                Branch::Leaf { data, .. } => f(data),
+                Branch::Bucket { items } => items.iter_mut().for_each(|(_, data)| f(data)),
+                Branch::Split { .. } | Branch::Skip { .. } => {}
+            }
+        }
+        self.parked.iter_mut().for_each(|(_, data)| f(data));
+        self.recalculate_aggregates();
+    }
+
+    /// Recalculates the aggregate of every split branch in the tree.
+    pub(crate) fn recalculate_aggregates(&mut self) {
+        if let (true, Some(root)) = (Self::AGGREGATES, self.root) {
+            self.recalculate_branch(root);
+        }
+    }
+
+    fn recalculate_branch(&mut self, branch: BranchKey) -> A {
+        match self.get_branch(branch) {
+            Branch::Leaf { .. } | Branch::Bucket { .. } => self.branch_aggregate(branch),
+            Branch::Skip { child, .. } => self.recalculate_branch(*child),
+            Branch::Split { children, .. } => {
+                let children = *children;
+                let new = children.iter().flatten().fold(A::default(), |acc, child| {
+                    acc.combine(&self.recalculate_branch(*child))
+                });
+                let Branch::Split { aggregate, .. } = self.get_branch_mut(branch) else {
+                    unreachable!()
+                };
+                *aggregate = new.clone();
+                new
+            }
+        }
+    }
+
+    fn refresh_branch(&mut self, branch: BranchKey, point: &PointData<P>) {
+        match self.get_branch(branch) {
+            Branch::Leaf { .. } | Branch::Bucket { .. } => {}
+            Branch::Skip {
+                point: skip_point,
+                point_depth,
+                child,
+                ..
+            } => {
+                if (point ^ skip_point).leading_zeros() >= *point_depth {
+                    self.refresh_branch(*child, point);
+                }
+            }
+            Branch::Split {
+                children, depth, ..
+            } => {
+                let children = *children;
+                if let Some(child) = children[point.nth(depth - 1) as usize] {
+                    self.refresh_branch(child, point);
+                }
+                let new = children.iter().flatten().fold(A::default(), |acc, child| {
+                    acc.combine(&self.branch_aggregate(*child))
+                });
+                let Branch::Split { aggregate, .. } = self.get_branch_mut(branch) else {
+                    unreachable!()
+                };
+                *aggregate = new;
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use std::ops::Range;
+
+use super::{
+    aggregate::Aggregate,
+    error::LoadError,
+    point::{ordered::OrderedBinary, Point, PointData},
+    saved::SavedOctree,
+    Branch, BranchKey, Octree,
+};
+
+pub(crate) const MAGIC: [u8; 8] = *b"MURMOCT\0";
+pub(crate) const VERSION: u16 = 1;
+pub(crate) const NODE_SIZE: usize = 16;
+
+pub(crate) const SPLIT: u8 = 0;
+pub(crate) const SKIP: u8 = 1;
+pub(crate) const LEAF: u8 = 2;
+pub(crate) const BUCKET: u8 = 3;
+
+/// Data which can be stored as a fixed number of bytes in the binary format used by
+/// [`Octree::to_bytes`].
+///
+/// This is implemented for the primitive number types (as little-endian bytes), `()` and arrays of
+/// them.
+pub trait BinaryPayload: Copy {
+    /// The number of bytes each value takes up.
+    const SIZE: usize;
+    /// Returns a name for the type which is saved with the tree, so that loading it as another type
+    /// of the same size (such as `f32` instead of `u32`) fails.
+    fn name() -> String;
+    /// Appends exactly [`SIZE`](Self::SIZE) bytes to `out`.
+    fn write_bytes(&self, out: &mut Vec<u8>);
+    /// Reads a value back from exactly [`SIZE`](Self::SIZE) bytes.
+    fn read_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_binary_payload {
+    ($($ty:ty),*) => {$(
+        impl BinaryPayload for $ty {
+            const SIZE: usize = std::mem::size_of::<$ty>();
+            fn name() -> String {
+                stringify!($ty).to_string()
+            }
+            fn write_bytes(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn read_bytes(bytes: &[u8]) -> Self {
+                Self::from_le_bytes(bytes.try_into().unwrap())
+            }
+        }
+    )*};
+}
+
+impl_binary_payload!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// Stored as a `u64` so that files are the same on every platform, values which are too large for
+/// this platform are read as `usize::MAX`.
+impl BinaryPayload for usize {
+    const SIZE: usize = 8;
+    fn name() -> String {
+        "usize".to_string()
+    }
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        (*self as u64).write_bytes(out);
+    }
+    fn read_bytes(bytes: &[u8]) -> Self {
+        usize::try_from(u64::read_bytes(bytes)).unwrap_or(usize::MAX)
+    }
+}
+
+impl BinaryPayload for () {
+    const SIZE: usize = 0;
+    fn name() -> String {
+        "()".to_string()
+    }
+    fn write_bytes(&self, _out: &mut Vec<u8>) {}
+    fn read_bytes(_bytes: &[u8]) -> Self {}
+}
+
+impl<T: BinaryPayload, const N: usize> BinaryPayload for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+    fn name() -> String {
+        format!("[{}; {N}]", T::name())
+    }
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        for value in self {
+            value.write_bytes(out);
+        }
+    }
+    fn read_bytes(bytes: &[u8]) -> Self {
+        std::array::from_fn(|i| T::read_bytes(&bytes[i * T::SIZE..(i + 1) * T::SIZE]))
+    }
+}
+
+/// A single branch in the node table, a split's children are stored next to each other and every
+/// node covers a contiguous range of items (as they are stored depth first)
+#[derive(Default)]
+pub(crate) struct Node {
+    pub(crate) kind: u8,
+    pub(crate) depth: u8, // The depth of a split or the point depth of a skip
+    pub(crate) occupied: u8,
+    pub(crate) children: u32, // The first child of a split, or the child of a skip
+    pub(crate) items: u32,
+    pub(crate) len: u32,
+}
+
+impl Node {
+    /// Reads the node at `index` of the node table
+    pub(crate) fn read(nodes: &[u8], index: usize) -> Node {
+        let bytes = &nodes[index * NODE_SIZE..(index + 1) * NODE_SIZE];
+        let [kind, depth, occupied, _] = <[u8; 4]>::read_bytes(&bytes[..4]);
+        let [children, items, len] = <[u32; 3]>::read_bytes(&bytes[4..]);
+        Node {
+            kind,
+            depth,
+            occupied,
+            children,
+            items,
+            len,
+        }
+    }
+
+    pub(crate) fn item_range(&self) -> Range<usize> {
+        self.items as usize..self.items as usize + self.len as usize
+    }
+}
+
+impl<D: BinaryPayload, P: Point, A> Octree<D, P, A> {
+    /// Saves the tree in a compact binary format which can be loaded with
+    /// [`from_bytes`](Self::from_bytes) much faster than adding every item again, or queried in
+    /// place with a [`SavedOctree`].
+    ///
+    /// The format is versioned and records the point and data types so that loading it as different
+    /// types fails rather than returning garbage. After a header there is a table of fixed size nodes
+    /// followed by all the points and then all the data.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1.0_f32, 2.0, 3.0], 7_u32);
+    /// let bytes = tree.to_bytes();
+    ///
+    /// let loaded: Octree<u32, [f32; 3]> = Octree::from_bytes(&bytes).unwrap();
+    /// assert_eq!(loaded.get_single(&[1.0, 2.0, 3.0]), Some(&7));
+    /// assert!(Octree::<u32, [f64; 3]>::from_bytes(&bytes).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut nodes = Vec::new();
+        let mut items = Vec::with_capacity(self.len());
+        if let Some(root) = self.root {
+            nodes.push(Node::default());
+            self.write_branch(root, 0, &mut nodes, &mut items);
+        }
+        items.extend(self.parked.iter().map(|(point, data)| (point, data)));
+        let parked = self.parked.len();
+
+        let name = P::Data::NAME;
+        let data_name = D::name();
+        let point_size = <<P::Data as OrderedBinary>::Ordered as BinaryPayload>::SIZE;
+        let mut out = Vec::with_capacity(
+            64 + name.len()
+                + data_name.len()
+                + nodes.len() * NODE_SIZE
+                + items.len() * (point_size * 3 + D::SIZE),
+        );
+        out.extend_from_slice(&MAGIC);
+        VERSION.write_bytes(&mut out);
+        P::MAX_DEPTH.write_bytes(&mut out);
+        (self.non_finite as u8).write_bytes(&mut out);
+        (point_size as u32).write_bytes(&mut out);
+        (D::SIZE as u32).write_bytes(&mut out);
+        self.bucket_size.write_bytes(&mut out);
+        nodes.len().write_bytes(&mut out);
+        (items.len() - parked).write_bytes(&mut out);
+        parked.write_bytes(&mut out);
+        (name.len() as u32).write_bytes(&mut out);
+        out.extend_from_slice(name.as_bytes());
+        (data_name.len() as u32).write_bytes(&mut out);
+        out.extend_from_slice(data_name.as_bytes());
+
+        for node in &nodes {
+            [node.kind, node.depth, node.occupied, 0].write_bytes(&mut out);
+            [node.children, node.items, node.len].write_bytes(&mut out);
+        }
+        for (point, _) in &items {
+            point.0.write_bytes(&mut out);
+        }
+        for (_, data) in &items {
+            data.write_bytes(&mut out);
+        }
+        out
+    }
+
+    /// Writes `branch` into the node at `node`, adding its items to `items`
+    fn write_branch<'a>(
+        &'a self,
+        branch: BranchKey,
+        node: usize,
+        nodes: &mut Vec<Node>,
+        items: &mut Vec<(&'a PointData<P>, &'a D)>,
+    ) {
+        let first = items.len();
+        let (kind, depth, occupied, children) = match self.get_branch(branch) {
+            Branch::Split {
+                children,
+                occupied,
+                depth,
+                ..
+            } => {
+                let start = nodes.len();
+                nodes.resize_with(start + occupied.count_ones() as usize, Node::default);
+                for (i, child) in children.iter().flatten().enumerate() {
+                    self.write_branch(*child, start + i, nodes, items);
+                }
+                (SPLIT, *depth, *occupied, start)
+            }
+            Branch::Skip {
+                point_depth, child, ..
+            } => {
+                let start = nodes.len();
+                nodes.push(Node::default());
+                self.write_branch(*child, start, nodes, items);
+                (SKIP, *point_depth, 0, start)
+            }
+            // Duplicates are stored together as a single leaf node
+            Branch::Leaf { .. } => {
+                let mut next = Some(branch);
+                while let Some(key) = next {
+                    let Branch::Leaf { point, data, child } = self.get_branch(key) else {
+                        unreachable!()
+                    };
+                    items.push((point, data));
+                    next = *child;
+                }
+                (LEAF, 0, 0, 0)
+            }
+            Branch::Bucket { items: bucket } => {
+                items.extend(bucket.iter().map(|(point, data)| (point, data)));
+                (BUCKET, 0, 0, 0)
+            }
+        };
+        nodes[node] = Node {
+            kind,
+            depth,
+            occupied,
+            children: children as u32,
+            items: first as u32,
+            len: (items.len() - first) as u32,
+        };
+    }
+}
+
+impl<D: BinaryPayload, P: Point, A: Aggregate<D, P>> Octree<D, P, A> {
+    /// Loads a tree saved with [`to_bytes`](Self::to_bytes).
+    ///
+    /// The bytes are checked by [`SavedOctree::from_bytes`] and then a branch is built for every
+    /// node, which skips working out where each item goes but still takes time proportional to the
+    /// size of the tree. To query a saved tree in place without building anything (such as from a
+    /// memory-mapped file) use the [`SavedOctree`] instead.
+    ///
+    /// # Errors
+    /// Returns a [`LoadError`] if the bytes aren't a tree saved with the same point and data types,
+    /// or if it isn't [valid](Self::validate), so corrupt data can't produce a broken tree.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        let saved = SavedOctree::<D, P>::from_bytes(bytes)?;
+        let mut octree = Octree {
+            non_finite: saved.non_finite_policy(),
+            bucket_size: saved.bucket_size(),
+            ..Octree::default()
+        };
+        if saved.node_count() > 0 {
+            octree.root = Some(octree.load_node(&saved, 0));
+        }
+        octree.parked = saved.parked_items().collect();
+
+        octree.validate().map_err(LoadError::Invalid)?;
+        octree.recalculate_aggregates();
+        Ok(octree)
+    }
+
+    /// Adds the branch for the node at `index` of the (already checked) saved tree
+    fn load_node(&mut self, saved: &SavedOctree<'_, D, P>, index: usize) -> BranchKey {
+        let node = saved.node(index);
+        let branch = match node.kind {
+            SPLIT => {
+                let mut children = [None; 8];
+                let mut next = node.children as usize;
+                for (i, child) in children.iter_mut().enumerate() {
+                    if node.occupied & (1 << i) != 0 {
+                        *child = Some(self.load_node(saved, next));
+                        next += 1;
+                    }
+                }
+                Branch::Split {
+                    children,
+                    occupied: node.occupied,
+                    depth: node.depth,
+                    len: node.len,
+                    aggregate: A::default(),
+                }
+            }
+            SKIP => Branch::Skip {
+                point: saved.point(node.items as usize),
+                point_depth: node.depth,
+                child: self.load_node(saved, node.children as usize),
+                len: node.len,
+            },
+            LEAF => {
+                // Build the chain of duplicates from the end so each leaf knows its child
+                let mut child = None;
+                for item in node.item_range().rev() {
+                    child = Some(self.add_branch(Branch::Leaf {
+                        point: saved.point(item),
+                        data: saved.data(item),
+                        child,
+                    }));
+                }
+                return child.expect("saved leaves aren't empty");
+            }
+            _ => Branch::Bucket {
+                items: node
+                    .item_range()
+                    .map(|item| (saved.point(item), saved.data(item)))
+                    .collect(),
+            },
+        };
+        self.add_branch(branch)
+    }
+}
+
+/// Reads sections from the start of the bytes
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+        if len > self.0.len() {
+            return Err(LoadError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    pub(crate) fn take_array(&mut self, count: usize, size: usize) -> Result<&'a [u8], LoadError> {
+        self.take(count.checked_mul(size).ok_or(LoadError::Truncated)?)
+    }
+
+    pub(crate) fn read<T: BinaryPayload>(&mut self) -> Result<T, LoadError> {
+        Ok(T::read_bytes(self.take(T::SIZE)?))
+    }
+
+    /// Reads a `usize` (saved as a `u64`), which may not fit on this platform
+    pub(crate) fn read_usize(&mut self) -> Result<usize, LoadError> {
+        usize::try_from(self.read::<u64>()?).map_err(|_| LoadError::InvalidHeader)
+    }
+}
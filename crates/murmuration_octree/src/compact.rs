@@ -0,0 +1,75 @@
+use nonmax::NonMaxU32;
+use slab::Slab;
+
+use super::{point::Point, Branch, BranchKey, Octree};
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Rebuilds the tree's branch storage so that it has no gaps and branches are laid out depth
+    /// first (in [Morton order](https://en.wikipedia.org/wiki/Z-order_curve)), keeping the storage's
+    /// capacity.
+    ///
+    /// After lots of items have been added and removed the tree gets
+    /// [fragmented](Self::fragmentation), with nearby branches scattered in memory, so this can
+    /// make queries such as [`within`](Self::within) noticeably faster in long-running programs.
+    pub fn compact(&mut self) {
+        let capacity = self.branches.capacity();
+        self.relayout(capacity);
+    }
+
+    /// Like [`compact`](Self::compact), but also frees any storage that isn't currently in use.
+    pub fn shrink_to_fit(&mut self) {
+        self.relayout(self.branches.len());
+        for (_, branch) in &mut self.branches {
+            if let Branch::Bucket { items } = branch {
+                items.shrink_to_fit();
+            }
+        }
+        self.parked.shrink_to_fit();
+    }
+
+    /// Moves every branch into new storage (with the given capacity) in depth first order
+    fn relayout(&mut self, capacity: usize) {
+        let order = self.depth_first_keys();
+        let mut new_keys = vec![None; self.slab_end];
+        for (new, old) in order.iter().enumerate() {
+            new_keys[Self::key_index(*old)] = Some(Self::index_key(new));
+        }
+
+        let mut old_branches = std::mem::replace(&mut self.branches, Slab::with_capacity(capacity));
+        for old in order {
+            let mut branch = old_branches.remove(Self::key_index(old));
+            branch
+                .map_keys(|key| new_keys[Self::key_index(key)])
+                .expect("every branch in the tree has a new key");
+            self.branches.insert(branch);
+        }
+        self.root = self.root.and_then(|root| new_keys[Self::key_index(root)]);
+        self.slab_end = self.branches.len();
+    }
+
+    /// Returns the key of every branch in the tree, with each branch followed by its children
+    fn depth_first_keys(&self) -> Vec<BranchKey> {
+        let mut order = Vec::with_capacity(self.branches.len());
+        let mut stack: Vec<BranchKey> = self.root.into_iter().collect();
+        while let Some(branch) = stack.pop() {
+            order.push(branch);
+            match self.get_branch(branch) {
+                // Pushed in reverse so that the first child is visited next
+                Branch::Split { children, .. } => stack.extend(children.iter().rev().flatten()),
+                Branch::Skip { child, .. } => stack.push(*child),
+                Branch::Leaf { child, .. } => stack.extend(*child),
+                Branch::Bucket { .. } => {}
+            }
+        }
+        order
+    }
+
+    fn key_index(key: BranchKey) -> usize {
+        u32::from(key.0) as usize
+    }
+
+    fn index_key(index: usize) -> BranchKey {
+        // The number of branches can't have changed so this can't overflow
+        BranchKey(NonMaxU32::new(index as u32).unwrap())
+    }
+}
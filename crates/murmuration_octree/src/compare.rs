@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{
+    point::{Point, PointData},
+    visit::VisitAction,
+    Octree,
+};
+
+/// The changes which turn one [`Octree`] into another, returned by [`Octree::diff`].
+///
+/// Items are matched up by their data, so an item whose data is in both trees but at a different
+/// point has been moved.
+#[derive(Clone, Debug)]
+pub struct OctreeDiff<'a, D, P: Point> {
+    /// The items only in the new tree, with their points.
+    pub added: Vec<([P::Data; 3], &'a D)>,
+    /// The items only in the old tree, with their points.
+    pub removed: Vec<([P::Data; 3], &'a D)>,
+    /// The items in both trees at different points, with their old and new points.
+    #[allow(clippy::type_complexity)]
+    pub moved: Vec<([P::Data; 3], [P::Data; 3], &'a D)>,
+}
+
+impl<D, P: Point> OctreeDiff<'_, D, P> {
+    /// Returns `true` if the trees had the same items.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Returns every item in the tree (including parked ones) with its point, in Morton order
+    fn point_items(&self) -> Vec<(&PointData<P>, &D)> {
+        let mut items = Vec::with_capacity(self.len());
+        self.visit(|node| {
+            items.extend(node.point_data());
+            VisitAction::Descend
+        });
+        items.extend(self.parked.iter().map(|(point, data)| (point, data)));
+        items
+    }
+}
+
+impl<D: Eq + Hash, P: Point, A> PartialEq for Octree<D, P, A> {
+    /// Returns `true` if both trees have the same items at the same points, regardless of how they
+    /// are stored (so the order they were added in, bucket size and aggregates don't matter).
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        let mut items = self.point_items();
+        let mut other_items = other.point_items();
+        items.sort_by_key(|(point, _)| point.0);
+        other_items.sort_by_key(|(point, _)| point.0);
+
+        // Compare the data at each point as a multiset, counting it when there are several
+        let mut counts = HashMap::new();
+        let mut start = 0;
+        while start < items.len() {
+            let point = items[start].0;
+            let end = start + items[start..].partition_point(|(p, _)| *p == point);
+            if other_items[start..end].iter().any(|(p, _)| *p != point) {
+                return false;
+            }
+            if end - start == 1 {
+                if items[start].1 != other_items[start].1 {
+                    return false;
+                }
+            } else {
+                counts.clear();
+                for (_, data) in &items[start..end] {
+                    *counts.entry(*data).or_insert(0_usize) += 1;
+                }
+                for (_, data) in &other_items[start..end] {
+                    match counts.get_mut(data) {
+                        Some(count) if *count > 0 => *count -= 1,
+                        _ => return false,
+                    }
+                }
+            }
+            start = end;
+        }
+        true
+    }
+}
+
+impl<D: Eq + Hash, P: Point, A> Eq for Octree<D, P, A> {}
+
+/// Some data with the points it is at in the old and new trees
+type Found<'a, D, P> = (&'a D, Vec<&'a PointData<P>>, Vec<&'a PointData<P>>);
+
+impl<D: Eq + Hash, P: Point, A> Octree<D, P, A> {
+    /// Returns the items added, removed and moved to turn this tree into `other`.
+    ///
+    /// Items are matched by their data, so this works best when every item's data is unique (such
+    /// as an id). If there are several equal items they are matched up arbitrarily.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut old = Octree::new();
+    /// old.add(&[1_u32, 2, 3], "stays");
+    /// old.add(&[4, 5, 6], "moves");
+    /// old.add(&[7, 8, 9], "leaves");
+    ///
+    /// let mut new = old.clone();
+    /// new.move_data(&[4, 5, 6], &[40, 5, 6], "moves");
+    /// new.remove(&[7, 8, 9], &"leaves");
+    /// new.add(&[1, 2, 3], "arrives");
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.added[0].1, &"arrives");
+    /// assert_eq!(diff.removed[0].1, &"leaves");
+    /// let (from, to, data) = &diff.moved[0];
+    /// assert_eq!((*from, *to, *data), ([4, 5, 6], [40, 5, 6], &"moves"));
+    /// ```
+    pub fn diff<'a>(&'a self, other: &'a Self) -> OctreeDiff<'a, D, P> {
+        // The points each piece of data is at in each tree, in the order they are first found
+        let mut indices = HashMap::new();
+        let mut found: Vec<Found<D, P>> = Vec::new();
+        for (new, items) in [(false, self.point_items()), (true, other.point_items())] {
+            for (point, data) in items {
+                let ind = *indices.entry(data).or_insert_with(|| {
+                    found.push((data, Vec::new(), Vec::new()));
+                    found.len() - 1
+                });
+                if new {
+                    found[ind].2.push(point);
+                } else {
+                    found[ind].1.push(point);
+                }
+            }
+        }
+
+        let mut diff = OctreeDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            moved: Vec::new(),
+        };
+        for (data, mut old_points, mut new_points) in found {
+            // Anything at the same point in both is unchanged
+            old_points.retain(|point| match new_points.iter().position(|p| p == point) {
+                Some(ind) => {
+                    new_points.swap_remove(ind);
+                    false
+                }
+                None => true,
+            });
+            let moves = old_points.len().min(new_points.len());
+            for (from, to) in old_points.drain(..moves).zip(new_points.drain(..moves)) {
+                diff.moved.push((from.to_array(), to.to_array(), data));
+            }
+            diff.removed
+                .extend(old_points.into_iter().map(|point| (point.to_array(), data)));
+            diff.added
+                .extend(new_points.into_iter().map(|point| (point.to_array(), data)));
+        }
+        diff
+    }
+}
@@ -0,0 +1,89 @@
+use super::{
+    point::{ordered::OrderedBinary, Point, PointData},
+    region::{Aabb, Overlap, Region, Sphere},
+    Branch, BranchKey, Octree,
+};
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Returns the number of items within `distance` of `point`.
+    ///
+    /// This is faster than counting [`within`](Self::within) as whole branches of the tree can be
+    /// counted at once when they are entirely in range.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn count_within(&self, point: &P, distance: P::Data) -> usize {
+        self.count_in(&Sphere {
+            centre: point.get_point(),
+            sqr_dist: distance.distance_squared(&P::Data::ZERO),
+        })
+    }
+
+    /// Returns the number of items between `min` and `max` (inclusive) on every axis.
+    pub fn count_in_aabb(&self, min: &P, max: &P) -> usize {
+        self.count_in(&Aabb {
+            min: min.get_point(),
+            max: max.get_point(),
+        })
+    }
+
+    fn count_in(&self, region: &impl Region<P>) -> usize {
+        self.root.map_or(0, |root| {
+            self.count_branch(root, &PointData::ZERO, region) as usize
+        })
+    }
+
+    /// Counts the items in `region` below `branch`, where `point` is in the same cell as `branch`
+    fn count_branch(
+        &self,
+        branch: BranchKey,
+        point: &PointData<P>,
+        region: &impl Region<P>,
+    ) -> u32 {
+        match self.get_branch(branch) {
+            Branch::Leaf { point, .. } => {
+                if region.contains(point) {
+                    self.branch_len(branch)
+                } else {
+                    0
+                }
+            }
+            Branch::Bucket { items } => items
+                .iter()
+                .filter(|(point, _)| region.contains(point))
+                .count() as u32,
+            Branch::Skip {
+                point,
+                point_depth,
+                child,
+                ..
+            } => self.count_cell(*child, point, *point_depth, region),
+            Branch::Split {
+                children,
+                depth: split_depth,
+                ..
+            } => (0..8)
+                .filter_map(|i| children[i as usize].map(|child| (i, child)))
+                .map(|(i, child)| {
+                    let child_point = point.combine_ind(i, *split_depth);
+                    self.count_cell(child, &child_point, *split_depth, region)
+                })
+                .sum(),
+        }
+    }
+
+    /// Like [`count_branch`](Self::count_branch) but counts the whole branch at once if its cell is
+    /// entirely inside `region`
+    fn count_cell(
+        &self,
+        branch: BranchKey,
+        point: &PointData<P>,
+        depth: u8,
+        region: &impl Region<P>,
+    ) -> u32 {
+        let (min, max) = point.cell_bounds(depth);
+        match region.overlap(&min, &max) {
+            Overlap::Outside => 0,
+            Overlap::Partial => self.count_branch(branch, point, region),
+            Overlap::Inside => self.branch_len(branch),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::{Debug, Write};
+
+use super::{
+    point::Point,
+    visit::{NodeKind, NodeView},
+    Octree,
+};
+
+/// The most items shown in each DOT node, so huge leaves don't swamp the graph
+const MAX_DOT_ITEMS: usize = 8;
+
+impl<D: Debug, P: Point, A> Octree<D, P, A>
+where
+    P::Data: Debug,
+{
+    /// Returns a [Graphviz](https://graphviz.org/) DOT graph of the tree's structure, showing the
+    /// kind and depth of every node along with the items in each leaf and bucket.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1_u8, 2, 3], "a");
+    /// tree.add(&[200, 2, 3], "b");
+    /// let dot = tree.to_dot();
+    /// assert!(dot.starts_with("digraph octree {"));
+    /// assert!(dot.contains("Split"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph octree {\n    node [shape=box, fontname=monospace];\n");
+        if let Some(root) = self.root_node() {
+            Self::dot_node(&root, &mut 0, &mut dot);
+        }
+        if !self.parked.is_empty() {
+            let items = dot_items(self.parked(), self.parked.len());
+            writeln!(
+                dot,
+                "    parked [label=\"Parked\\n{items}\", style=dashed];"
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes `node` and everything below it, returning its id
+    fn dot_node(node: &NodeView<'_, D, P, A>, next_id: &mut usize, dot: &mut String) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+        let kind = node.kind();
+        let mut label = format!("{kind:?}\\ndepth {}\\n{} items", node.depth(), node.len());
+        if kind == NodeKind::Leaf {
+            let (point, _) = node.cell();
+            write!(label, "\\n{}", dot_escape(&format!("{point:?}"))).unwrap();
+        }
+        if matches!(kind, NodeKind::Leaf | NodeKind::Bucket) {
+            write!(label, "\\n{}", dot_items(node.items(), node.len())).unwrap();
+        }
+        writeln!(dot, "    n{id} [label=\"{label}\"];").unwrap();
+
+        // Split edges are labelled with the index of the child's cell
+        let occupied = node.occupied();
+        let mut indices = (0..8).filter(|i| occupied & (1 << i) != 0);
+        for child in node.children() {
+            let child_id = Self::dot_node(&child, next_id, dot);
+            match indices.next() {
+                Some(ind) => writeln!(dot, "    n{id} -> n{child_id} [label=\"{ind}\"];"),
+                None => writeln!(dot, "    n{id} -> n{child_id};"),
+            }
+            .unwrap();
+        }
+        id
+    }
+
+    /// Returns a JSON description of the tree's structure, with the kind, depth and cell bounds of
+    /// every node and the point and data (formatted with `Debug`) of every item.
+    ///
+    /// Numbers which aren't valid JSON (such as infinite cell bounds) are written as strings.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1_u8, 2, 3], "a");
+    /// assert_eq!(
+    ///     tree.to_json(),
+    ///     r#"{"len":1,"parked":[],"root":{"kind":"Leaf","depth":8,"level":0,"len":1,"min":[1,2,3],"max":[1,2,3],"items":[{"point":[1,2,3],"data":"\"a\""}]}}"#
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"len\":{},\"parked\":", self.len());
+        json_items(
+            self.parked
+                .iter()
+                .map(|(point, data)| (point.to_array(), data)),
+            &mut json,
+        );
+        json.push_str(",\"root\":");
+        match self.root_node() {
+            Some(root) => Self::json_node(&root, &mut json),
+            None => json.push_str("null"),
+        }
+        json.push('}');
+        json
+    }
+
+    fn json_node(node: &NodeView<'_, D, P, A>, json: &mut String) {
+        let (min, max) = node.cell();
+        write!(
+            json,
+            "{{\"kind\":\"{:?}\",\"depth\":{},\"level\":{},\"len\":{},\"min\":{},\"max\":{}",
+            node.kind(),
+            node.depth(),
+            node.level(),
+            node.len(),
+            json_point(&min),
+            json_point(&max)
+        )
+        .unwrap();
+        match node.kind() {
+            NodeKind::Leaf | NodeKind::Bucket => {
+                json.push_str(",\"items\":");
+                json_items(node.points(), json);
+            }
+            NodeKind::Split | NodeKind::Skip => {
+                json.push_str(",\"children\":[");
+                for (i, child) in node.children().enumerate() {
+                    if i > 0 {
+                        json.push(',');
+                    }
+                    Self::json_node(&child, json);
+                }
+                json.push(']');
+            }
+        }
+        json.push('}');
+    }
+}
+
+/// Lists up to `MAX_DOT_ITEMS` of the `len` items
+fn dot_items<D: Debug>(items: impl Iterator<Item = D>, len: usize) -> String {
+    let mut list = items
+        .take(MAX_DOT_ITEMS)
+        .map(|item| dot_escape(&format!("{item:?}")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if len > MAX_DOT_ITEMS {
+        write!(list, ", ... ({} more)", len - MAX_DOT_ITEMS).unwrap();
+    }
+    list
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn json_items<'a, N: Debug, D: Debug + 'a>(
+    items: impl Iterator<Item = ([N; 3], &'a D)>,
+    json: &mut String,
+) {
+    json.push('[');
+    for (i, (point, data)) in items.enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write!(
+            json,
+            "{{\"point\":{},\"data\":{}}}",
+            json_point(&point),
+            json_string(&format!("{data:?}"))
+        )
+        .unwrap();
+    }
+    json.push(']');
+}
+
+fn json_point<N: Debug>(point: &[N; 3]) -> String {
+    let [x, y, z] = point.each_ref().map(|n| {
+        let n = format!("{n:?}");
+        if n.parse::<f64>().is_ok_and(f64::is_finite) {
+            n
+        } else {
+            json_string(&n)
+        }
+    });
+    format!("[{x},{y},{z}]")
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -0,0 +1,225 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+/// The error returned by [`Octree::try_add`](crate::Octree::try_add), this gives back the data that
+/// couldn't be added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddError<D> {
+    /// The point had a NaN or infinite coordinate (and the tree isn't using
+    /// [`NonFinitePolicy::Park`](crate::NonFinitePolicy::Park)).
+    NonFinite(D),
+    /// The tree already holds its maximum number of items (see
+    /// [`Octree::set_max_items`](crate::Octree::set_max_items)).
+    Full(D),
+    /// The tree has run out of keys for its branches (there can be at most `2^32 - 1`).
+    OutOfKeys(D),
+}
+
+impl<D> AddError<D> {
+    /// Returns the data that was rejected.
+    pub fn into_data(self) -> D {
+        match self {
+            AddError::NonFinite(data) | AddError::Full(data) | AddError::OutOfKeys(data) => data,
+        }
+    }
+}
+
+impl<D> Display for AddError<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddError::NonFinite(_) => write!(f, "point has a NaN or infinite coordinate"),
+            AddError::Full(_) => write!(f, "the octree already holds its maximum number of items"),
+            AddError::OutOfKeys(_) => write!(f, "the octree has run out of branch keys"),
+        }
+    }
+}
+
+impl<D: Debug> Error for AddError<D> {}
+
+/// A broken invariant found by [`Octree::validate`](crate::Octree::validate), where `branch` is the
+/// index of the offending branch in the tree's storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValidationError {
+    /// A branch refers to a child which doesn't exist.
+    MissingChild {
+        /// The parent branch (or `None` for the root).
+        branch: Option<usize>,
+    },
+    /// A branch is the child of more than one parent (or of itself).
+    SharedChild {
+        /// The branch that was reached twice.
+        branch: usize,
+    },
+    /// A split branch's `occupied` flags don't match its children, or it has fewer than two (or a
+    /// bucket is empty).
+    BadChildren {
+        /// The split branch.
+        branch: usize,
+    },
+    /// A skip branch's child isn't a split branch.
+    BadSkipChild {
+        /// The skip branch.
+        branch: usize,
+    },
+    /// A split or skip branch's depth doesn't fit with the branches above it.
+    BadDepth {
+        /// The split or skip branch.
+        branch: usize,
+    },
+    /// A skip, leaf or bucket branch's point isn't inside the cell its parent puts it in (or a
+    /// duplicate leaf has a different point).
+    BadPoint {
+        /// The skip or leaf branch.
+        branch: usize,
+    },
+    /// A split or skip branch's cached length doesn't match the number of items below it.
+    BadLength {
+        /// The split or skip branch.
+        branch: usize,
+    },
+    /// Some branches can't be reached from the root.
+    Orphaned {
+        /// The number of unreachable branches.
+        count: usize,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::MissingChild {
+                branch: Some(branch),
+            } => {
+                write!(f, "branch {branch} has a child which doesn't exist")
+            }
+            ValidationError::MissingChild { branch: None } => write!(f, "the root doesn't exist"),
+            ValidationError::SharedChild { branch } => {
+                write!(f, "branch {branch} has more than one parent")
+            }
+            ValidationError::BadChildren { branch } => {
+                write!(f, "branch {branch} has invalid children")
+            }
+            ValidationError::BadSkipChild { branch } => {
+                write!(f, "skip branch {branch} doesn't have a split child")
+            }
+            ValidationError::BadDepth { branch } => {
+                write!(f, "branch {branch} has an inconsistent depth")
+            }
+            ValidationError::BadPoint { branch } => {
+                write!(f, "branch {branch} has a point outside its cell")
+            }
+            ValidationError::BadLength { branch } => {
+                write!(f, "branch {branch} has an incorrect length")
+            }
+            ValidationError::Orphaned { count } => {
+                write!(f, "{count} branches can't be reached from the root")
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// The error returned by [`Octree::from_bytes`](crate::Octree::from_bytes) when the bytes aren't a
+/// tree of the right type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LoadError {
+    /// The bytes don't start with the header of a saved tree.
+    NotAnOctree,
+    /// The tree was saved in a newer (or unknown) version of the format.
+    UnsupportedVersion {
+        /// The version of the saved tree.
+        version: u16,
+    },
+    /// A field of the header has a value which isn't allowed (or is too large for this platform).
+    InvalidHeader,
+    /// The tree was saved with a different point type.
+    WrongPointType,
+    /// The tree was saved with a different data type.
+    WrongDataType,
+    /// The bytes end before the end of the tree.
+    Truncated,
+    /// A node in the saved tree is malformed or refers to children or items which don't exist.
+    BadNode {
+        /// The index of the node in the saved node table.
+        node: usize,
+    },
+    /// The saved tree's structure is invalid.
+    Invalid(ValidationError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::NotAnOctree => write!(f, "the data isn't a saved octree"),
+            LoadError::UnsupportedVersion { version } => {
+                write!(f, "the octree was saved with unsupported version {version}")
+            }
+            LoadError::InvalidHeader => write!(f, "the octree's header is invalid"),
+            LoadError::WrongPointType => {
+                write!(f, "the octree was saved with a different point type")
+            }
+            LoadError::WrongDataType => {
+                write!(f, "the octree was saved with a different data type")
+            }
+            LoadError::Truncated => write!(f, "the data ends before the end of the octree"),
+            LoadError::BadNode { node } => write!(f, "node {node} is malformed"),
+            LoadError::Invalid(err) => write!(f, "the saved octree is invalid: {err}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Invalid(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The error returned when loading a point cloud with
+/// [`Octree::load_points`](crate::Octree::load_points) fails.
+#[cfg(feature = "io")]
+#[derive(Debug)]
+pub enum PointCloudError {
+    /// Reading the file failed.
+    Io(std::io::Error),
+    /// The file isn't in the expected format.
+    Parse {
+        /// The line number (starting from 1) where the problem was found.
+        line: usize,
+        /// What the problem was.
+        message: String,
+    },
+    /// The file uses a feature of the format which isn't supported.
+    Unsupported(String),
+}
+
+#[cfg(feature = "io")]
+impl From<std::io::Error> for PointCloudError {
+    fn from(err: std::io::Error) -> Self {
+        PointCloudError::Io(err)
+    }
+}
+
+#[cfg(feature = "io")]
+impl Display for PointCloudError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PointCloudError::Io(err) => write!(f, "failed to read the point cloud: {err}"),
+            PointCloudError::Parse { line, message } => write!(f, "line {line}: {message}"),
+            PointCloudError::Unsupported(feature) => write!(f, "unsupported: {feature}"),
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl Error for PointCloudError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PointCloudError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
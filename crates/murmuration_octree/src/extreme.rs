@@ -0,0 +1,198 @@
+use std::collections::BinaryHeap;
+
+use super::{
+    nearest::{Candidate, Entry},
+    point::{ordered::OrderedBinary, unsigned::Unsigned, Point, PointData},
+    region::distance_bounds,
+    visit::{NodeKind, VisitAction},
+    Octree,
+};
+
+type Ordered<P> = <<P as Point>::Data as OrderedBinary>::Ordered;
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Returns the item furthest from `point` (or one of them if there is a tie), or `None` if the
+    /// tree is empty.
+    ///
+    /// Items whose distance is NaN and [parked](Self::parked) items are never returned.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1.0_f32, 2.0, 3.0], "a");
+    /// tree.add(&[10.0, 2.0, 3.0], "b");
+    /// assert_eq!(tree.farthest(&[7.0, 0.0, 0.0]), Some(&"a"));
+    /// ```
+    pub fn farthest(&self, point: &P) -> Option<&D> {
+        let centre = point.get_point();
+        let (_, data) = self.first_by(
+            |min, max| {
+                let (_, furthest) = distance_bounds(&centre, min, max);
+                // Cells spanning NaNs have NaN bounds, which could be anything
+                if furthest.is_irrelevant() {
+                    Ordered::<P>::from(0)
+                } else {
+                    largest_first(furthest.to_ordered())
+                }
+            },
+            |point| {
+                let distance = point.distance_squared(&centre);
+                (!distance.is_irrelevant()).then(|| largest_first(distance.to_ordered()))
+            },
+        )?;
+        Some(data)
+    }
+
+    /// Returns the item with the smallest coordinate on `axis` (`0`, `1` or `2`) with its point, or
+    /// `None` if the tree is empty.
+    ///
+    /// Items with a NaN coordinate on `axis` and [parked](Self::parked) items are never returned.
+    ///
+    /// # Panics
+    /// If `axis` is more than `2`.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1_i32, -5, 3], "a");
+    /// tree.add(&[-2, 4, 8], "b");
+    /// assert_eq!(tree.min_by_axis(0), Some(([-2, 4, 8], &"b")));
+    /// assert_eq!(tree.min_by_axis(1), Some(([1, -5, 3], &"a")));
+    /// ```
+    pub fn min_by_axis(&self, axis: usize) -> Option<([P::Data; 3], &D)> {
+        assert!(axis <= 2, "axis {axis} is out of range");
+        let (point, data) = self.first_by(
+            |min, _| min.0[axis],
+            |point| relevant_coord(point, axis).then_some(point.0[axis]),
+        )?;
+        Some((point.to_array(), data))
+    }
+
+    /// Returns the item with the largest coordinate on `axis` (`0`, `1` or `2`) with its point, or
+    /// `None` if the tree is empty.
+    ///
+    /// Items with a NaN coordinate on `axis` and [parked](Self::parked) items are never returned.
+    ///
+    /// # Panics
+    /// If `axis` is more than `2`.
+    pub fn max_by_axis(&self, axis: usize) -> Option<([P::Data; 3], &D)> {
+        assert!(axis <= 2, "axis {axis} is out of range");
+        let (point, data) = self.first_by(
+            |_, max| largest_first(max.0[axis]),
+            |point| relevant_coord(point, axis).then(|| largest_first(point.0[axis])),
+        )?;
+        Some((point.to_array(), data))
+    }
+
+    /// Returns the smallest and largest corners (inclusive) of the box around every item in the
+    /// tree, or `None` if it is empty (or only has [parked](Self::parked) items).
+    ///
+    /// Infinite coordinates are included like any other, but NaN coordinates are ignored unless
+    /// every coordinate on an axis is NaN, in which case that axis is NaN in both corners.
+    ///
+    /// This is found in a single pass over the tree which skips any node whose cell is already
+    /// inside the box found so far, and doesn't allocate. That usually leaves only the edges of the
+    /// tree to look at, but nothing is cached, so it is O(n) in the worst case (such as when most
+    /// items are on the surface of the box, or in a few large buckets). Store the result rather than
+    /// calling this repeatedly on a large tree that hasn't changed.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1_i32, -5, 3], "a");
+    /// tree.add(&[-2, 4, 8], "b");
+    /// assert_eq!(tree.bounds(), Some(([-2, -5, 3], [1, 4, 8])));
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn bounds(&self) -> Option<([P::Data; 3], [P::Data; 3])> {
+        // The extremes of the ordered coordinates on each axis, and a NaN one if there has been any
+        let mut bounds: [Option<(Ordered<P>, Ordered<P>)>; 3] = [None; 3];
+        let mut nan = [None; 3];
+        self.visit(|node| {
+            let (min, max) = node.cell_data();
+            let inside = (0..=2)
+                .all(|i| bounds[i].is_some_and(|(low, high)| low <= min.0[i] && max.0[i] <= high));
+            if inside {
+                return VisitAction::Skip;
+            }
+            for (point, _) in node.point_data() {
+                for (i, coord) in point.0.iter().enumerate() {
+                    if !relevant_coord(point, i) {
+                        nan[i] = Some(*coord);
+                    } else if let Some((low, high)) = &mut bounds[i] {
+                        *low = (*low).min(*coord);
+                        *high = (*high).max(*coord);
+                    } else {
+                        bounds[i] = Some((*coord, *coord));
+                    }
+                }
+            }
+            VisitAction::Descend
+        });
+
+        let mut min = [P::Data::ZERO, P::Data::ZERO, P::Data::ZERO];
+        let mut max = min.clone();
+        for i in 0..=2 {
+            let (low, high) = bounds[i].or(nan[i].map(|nan| (nan, nan)))?;
+            min[i] = P::Data::from_ordered(low);
+            max[i] = P::Data::from_ordered(high);
+        }
+        Some((min, max))
+    }
+
+    /// Returns the item with the smallest `item_key` (skipping any where it is `None`), searching
+    /// the tree best first where `cell_key` gives a lower bound of the keys in the cell between
+    /// `min` and `max`
+    fn first_by(
+        &self,
+        cell_key: impl Fn(&PointData<P>, &PointData<P>) -> Ordered<P>,
+        item_key: impl Fn(&PointData<P>) -> Option<Ordered<P>>,
+    ) -> Option<(&PointData<P>, &D)> {
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate {
+            distance: Ordered::<P>::from(0),
+            entry: Entry::Node(self.root_node()?),
+        });
+        while let Some(candidate) = heap.pop() {
+            let node = match candidate.entry {
+                Entry::Item(point, data) => return Some((point, data)),
+                Entry::Node(node) => node,
+            };
+            if matches!(node.kind(), NodeKind::Leaf | NodeKind::Bucket) {
+                for (point, data) in node.point_data() {
+                    if let Some(key) = item_key(point) {
+                        heap.push(Candidate {
+                            distance: key,
+                            entry: Entry::Item(point, data),
+                        });
+                    }
+                }
+            } else {
+                for child in node.children() {
+                    let (min, max) = child.cell_data();
+                    heap.push(Candidate {
+                        distance: cell_key(&min, &max),
+                        entry: Entry::Node(child),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Flips the order of `key` so that the largest comes first
+fn largest_first<O: Unsigned>(key: O) -> O {
+    key ^ O::MAX
+}
+
+/// Returns `false` if the coordinate of `point` on `axis` is NaN
+fn relevant_coord<P: Point>(point: &PointData<P>, axis: usize) -> bool {
+    !P::Data::from_ordered(point.0[axis]).is_irrelevant()
+}
@@ -0,0 +1,332 @@
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+use super::{
+    point::{ordered::OrderedBinary, Point, PointData},
+    region::{Aabb, Overlap, Region, Sphere},
+    Branch, BranchKey, Octree,
+};
+
+/// A read-only copy of an [`Octree`] stored in flat arrays, returned by [`Octree::freeze`].
+///
+/// This is faster to query and smaller than an `Octree` as there is no empty space to keep track of and
+/// no links between branches, so it is useful for things which never move such as spawn points or level
+/// geometry.
+pub struct FrozenOctree<D, P: Point> {
+    nodes: Vec<FrozenNode>, // The root is first and each split's children are stored together
+    points: Vec<PointData<P>>,
+    data: Vec<D>, // Items are in depth first order so every node covers a contiguous range of them
+    parked: Vec<(PointData<P>, D)>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct FrozenNode {
+    depth: u8,    // The number of leading bits shared by everything in this node's cell
+    occupied: u8, // Which children exist (bitflags), 0 if this node holds items directly
+    children: u32,
+    items: u32,
+    len: u32,
+}
+
+impl FrozenNode {
+    fn items(&self) -> Range<usize> {
+        self.items as usize..(self.items + self.len) as usize
+    }
+
+    /// Returns the index of the child in direction `ind` if it exists
+    fn child(&self, ind: u8) -> Option<usize> {
+        (self.occupied & (1 << ind) != 0).then(|| {
+            let before = self.occupied & ((1 << ind) - 1);
+            self.children as usize + before.count_ones() as usize
+        })
+    }
+}
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Converts this into a read-only [`FrozenOctree`], which is faster to query.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1_u32, 2, 3], "spawn");
+    /// tree.add(&[100, 2, 3], "chest");
+    /// let frozen = tree.freeze();
+    /// assert_eq!(frozen.within(&[0, 0, 0], 10).collect::<Vec<_>>(), [&"spawn"]);
+    /// ```
+    pub fn freeze(mut self) -> FrozenOctree<D, P> {
+        let len = self.root.map_or(0, |root| self.branch_len(root) as usize);
+        let mut frozen = FrozenOctree {
+            nodes: Vec::new(),
+            points: Vec::with_capacity(len),
+            data: Vec::with_capacity(len),
+            parked: std::mem::take(&mut self.parked),
+        };
+        if let Some(root) = self.root {
+            frozen.nodes.push(FrozenNode::default());
+            self.freeze_branch(root, 0, 0, &mut frozen);
+        }
+        frozen.nodes.shrink_to_fit();
+        frozen
+    }
+
+    /// Moves `branch` (whose cell shares the first `depth` bits) into the node at `node`
+    fn freeze_branch(
+        &mut self,
+        branch: BranchKey,
+        depth: u8,
+        node: usize,
+        frozen: &mut FrozenOctree<D, P>,
+    ) {
+        let key = u32::from(branch.0) as usize;
+        let items = frozen.data.len() as u32;
+        match self.branches.remove(key) {
+            Branch::Split {
+                children,
+                occupied,
+                depth: split_depth,
+                ..
+            } => {
+                let first = frozen.nodes.len();
+                let count = occupied.count_ones() as usize;
+                frozen.nodes.resize(first + count, FrozenNode::default());
+                for (i, child) in children.iter().flatten().enumerate() {
+                    self.freeze_branch(*child, split_depth, first + i, frozen);
+                }
+                frozen.nodes[node] = FrozenNode {
+                    depth: split_depth - 1,
+                    occupied,
+                    children: first as u32,
+                    items,
+                    len: frozen.data.len() as u32 - items,
+                };
+            }
+            // The child's cell is just made smaller, the prefix is checked using its first item
+            Branch::Skip {
+                point_depth, child, ..
+            } => self.freeze_branch(child, point_depth, node, frozen),
+            Branch::Leaf { point, data, child } => {
+                frozen.points.push(point);
+                frozen.data.push(data);
+                let mut next = child;
+                while let Some(duplicate) = next {
+                    let key = u32::from(duplicate.0) as usize;
+                    let Branch::Leaf {
+                        point, data, child, ..
+                    } = self.branches.remove(key)
+                    else {
+                        unreachable!()
+                    };
+                    frozen.points.push(point);
+                    frozen.data.push(data);
+                    next = child;
+                }
+                frozen.nodes[node] = FrozenNode {
+                    depth: P::MAX_DEPTH,
+                    occupied: 0,
+                    children: 0,
+                    items,
+                    len: frozen.data.len() as u32 - items,
+                };
+            }
+            Branch::Bucket { items: bucket } => {
+                for (point, data) in bucket {
+                    frozen.points.push(point);
+                    frozen.data.push(data);
+                }
+                frozen.nodes[node] = FrozenNode {
+                    depth,
+                    occupied: 0,
+                    children: 0,
+                    items,
+                    len: frozen.data.len() as u32 - items,
+                };
+            }
+        }
+    }
+}
+
+impl<D, P: Point> FrozenOctree<D, P> {
+    /// Returns the number of items in the tree (including any [parked](Self::parked) ones).
+    pub fn len(&self) -> usize {
+        self.data.len() + self.parked.len()
+    }
+
+    /// Returns `true` if there are no items in the tree.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.parked.is_empty()
+    }
+
+    /// Returns all the items which were parked because their point wasn't finite.
+    pub fn parked(&self) -> impl Iterator<Item = &D> {
+        self.parked.iter().map(|(_, data)| data)
+    }
+
+    /// Returns all the items (not including parked ones) and their points, in Morton order.
+    pub fn iter(&self) -> impl Iterator<Item = ([P::Data; 3], &D)> {
+        self.points.iter().map(PointData::to_array).zip(&self.data)
+    }
+
+    /// Returns all items at the given `point`.
+    pub fn get(&self, point: &P) -> impl Iterator<Item = &D> {
+        let point = point.get_point();
+        let items = self.get_items(&point);
+        self.points[items.clone()]
+            .iter()
+            .zip(&self.data[items])
+            .filter(move |(p, _)| **p == point)
+            .map(|(_, data)| data)
+    }
+
+    /// Returns one of the items at the given `point` or `None` if there aren't any.
+    pub fn get_single(&self, point: &P) -> Option<&D> {
+        self.get(point).next()
+    }
+
+    /// Returns the range of items in the node which `point` would be in
+    fn get_items(&self, point: &PointData<P>) -> Range<usize> {
+        let Some(mut node) = self.nodes.first() else {
+            return 0..0;
+        };
+        loop {
+            if (point ^ &self.points[node.items as usize]).leading_zeros() < node.depth {
+                return 0..0;
+            }
+            if node.occupied == 0 {
+                return node.items();
+            }
+            match node.child(point.nth(node.depth)) {
+                Some(child) => node = &self.nodes[child],
+                None => return 0..0,
+            }
+        }
+    }
+
+    /// Returns all items within `distance` of `point`, in an unspecified order.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn within(&self, point: &P, distance: P::Data) -> impl Iterator<Item = &D> {
+        FrozenIter {
+            frozen: self,
+            region: Sphere {
+                centre: point.get_point(),
+                sqr_dist: distance.distance_squared(&P::Data::ZERO),
+            },
+            stack: if self.nodes.is_empty() {
+                vec![]
+            } else {
+                vec![0]
+            },
+            items: 0..0,
+            check: false,
+        }
+    }
+
+    /// Returns all items between `min` and `max` (inclusive) on every axis, in an unspecified order.
+    pub fn in_aabb(&self, min: &P, max: &P) -> impl Iterator<Item = &D> {
+        FrozenIter {
+            frozen: self,
+            region: Aabb {
+                min: min.get_point(),
+                max: max.get_point(),
+            },
+            stack: if self.nodes.is_empty() {
+                vec![]
+            } else {
+                vec![0]
+            },
+            items: 0..0,
+            check: false,
+        }
+    }
+
+    /// Returns the number of items within `distance` of `point`.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn count_within(&self, point: &P, distance: P::Data) -> usize {
+        self.count_in(&Sphere {
+            centre: point.get_point(),
+            sqr_dist: distance.distance_squared(&P::Data::ZERO),
+        })
+    }
+
+    /// Returns the number of items between `min` and `max` (inclusive) on every axis.
+    pub fn count_in_aabb(&self, min: &P, max: &P) -> usize {
+        self.count_in(&Aabb {
+            min: min.get_point(),
+            max: max.get_point(),
+        })
+    }
+
+    fn count_in(&self, region: &impl Region<P>) -> usize {
+        let mut count = 0;
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            match self.overlap(node, region) {
+                Overlap::Outside => {}
+                Overlap::Inside => count += node.len as usize,
+                Overlap::Partial if node.occupied == 0 => {
+                    count += self.points[node.items()]
+                        .iter()
+                        .filter(|point| region.contains(point))
+                        .count();
+                }
+                Overlap::Partial => {
+                    let children = node.children as usize;
+                    stack.extend(children..children + node.occupied.count_ones() as usize);
+                }
+            }
+        }
+        count
+    }
+
+    fn overlap(&self, node: &FrozenNode, region: &impl Region<P>) -> Overlap {
+        let (min, max) = self.points[node.items as usize].cell_bounds(node.depth);
+        region.overlap(&min, &max)
+    }
+}
+
+struct FrozenIter<'a, D, P: Point, R> {
+    frozen: &'a FrozenOctree<D, P>,
+    region: R,
+    stack: Vec<usize>,
+    items: Range<usize>, // The items we are currently going through
+    check: bool,         // Whether the current items need to be checked against the region
+}
+
+impl<'a, D, P: Point, R: Region<P>> Iterator for FrozenIter<'a, D, P, R> {
+    type Item = &'a D;
+    fn next(&mut self) -> Option<&'a D> {
+        loop {
+            for item in self.items.by_ref() {
+                if !self.check || self.region.contains(&self.frozen.points[item]) {
+                    return Some(&self.frozen.data[item]);
+                }
+            }
+
+            let node = &self.frozen.nodes[self.stack.pop()?];
+            match self.frozen.overlap(node, &self.region) {
+                Overlap::Outside => {}
+                Overlap::Inside => {
+                    self.items = node.items();
+                    self.check = false;
+                }
+                Overlap::Partial if node.occupied == 0 => {
+                    self.items = node.items();
+                    self.check = true;
+                }
+                Overlap::Partial => {
+                    let children = node.children as usize;
+                    self.stack
+                        .extend(children..children + node.occupied.count_ones() as usize);
+                }
+            }
+        }
+    }
+}
+
+impl<D, P: Point, R: Region<P>> FusedIterator for FrozenIter<'_, D, P, R> {}
@@ -5,31 +5,48 @@ use super::{
     Branch, BranchKey, Octree,
 };
 
-struct GetIter<'a, D, P: Point> {
-    octree: &'a Octree<D, P>,
+struct GetIter<'a, D, P: Point, A> {
+    octree: &'a Octree<D, P, A>,
     leaf: Option<BranchKey>,
+    point: PointData<P>,
+    ind: usize, // The next item to check if the leaf is a bucket
 }
 
-impl<'a, D, P: Point> Iterator for GetIter<'a, D, P> {
+impl<'a, D, P: Point, A> Iterator for GetIter<'a, D, P, A> {
     type Item = &'a D;
     fn next(&mut self) -> Option<&'a D> {
-        if let Some(key) = self.leaf {
-            if let Branch::Leaf { data, child, .. } = self.octree.get_branch(key) {
+        match self.octree.get_branch(self.leaf?) {
+            Branch::Leaf { data, child, .. } => {
                 self.leaf = *child;
-                return Some(data);
+                Some(data)
             }
+            Branch::Bucket { items } => {
+                let found = items[self.ind..].iter().position(|(p, _)| p == &self.point);
+                if let Some(found) = found {
+                    self.ind += found + 1;
+                    Some(&items[self.ind - 1].1)
+                } else {
+                    self.leaf = None;
+                    None
+                }
+            }
+            _ => None,
         }
-        None
     }
 }
 
-impl<D, P: Point> FusedIterator for GetIter<'_, D, P> {}
+impl<D, P: Point, A> FusedIterator for GetIter<'_, D, P, A> {}
 
-impl<D, P: Point> Octree<D, P> {
+impl<D, P: Point, A> Octree<D, P, A> {
     /// Returns all items at the given `point`.
     pub fn get(&self, point: &P) -> impl Iterator<Item = &D> {
-        let leaf = self.get_leaf(&point.get_point());
-        GetIter { octree: self, leaf }
+        let point = point.get_point();
+        GetIter {
+            octree: self,
+            leaf: self.get_leaf(&point),
+            point,
+            ind: 0,
+        }
     }
 
     /// Returns one of the items at the given `point` or `None` if there aren't any.
@@ -48,10 +65,14 @@ impl<D, P: Point> Octree<D, P> {
                 } => {
                     return (point == skip_point).then_some(branch);
                 }
+                Branch::Bucket { items } => {
+                    return items.iter().any(|(p, _)| p == point).then_some(branch);
+                }
                 Branch::Skip {
                     point: skip_point,
                     point_depth: skip_depth,
                     child,
+                    ..
                 } => {
                     let shared = (point ^ skip_point).leading_zeros();
                     if shared >= *skip_depth {
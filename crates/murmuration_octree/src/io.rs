@@ -0,0 +1,444 @@
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+
+use super::{error::PointCloudError, visit::VisitAction, Octree};
+
+/// A file format for point clouds, see [`Octree::load_points`] and [`Octree::write_points`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointFormat {
+    /// The [PLY](https://paulbourke.net/dataformats/ply/) format, which is loaded from either ASCII or
+    /// binary files and written as ASCII.
+    Ply,
+    /// Like [`Ply`](Self::Ply) except it is written as binary (little-endian), which is smaller and
+    /// faster to load.
+    BinaryPly,
+    /// Whitespace separated numbers with one point per line, starting with its `x`, `y` and `z`.
+    Xyz,
+    /// Comma separated numbers with one point per line and an optional header row naming the `x`,
+    /// `y` and `z` columns (otherwise the first three columns are used).
+    Csv,
+}
+
+impl Octree<usize, [f32; 3]> {
+    /// Loads a point cloud where each item is the index of its point in the file.
+    ///
+    /// # Errors
+    /// Returns a [`PointCloudError`] if reading fails or the file isn't in the given `format`.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::{Octree, PointFormat};
+    ///
+    /// let file = "x,y,z\n0,0,0\n1.5,2,-3\n";
+    /// let tree = Octree::load_points(file.as_bytes(), PointFormat::Csv).unwrap();
+    /// assert_eq!(tree.get_single(&[1.5, 2.0, -3.0]), Some(&1));
+    /// ```
+    pub fn load_points(reader: impl Read, format: PointFormat) -> Result<Self, PointCloudError> {
+        Self::load_points_with(reader, format, |index, _| index)
+    }
+}
+
+impl<D> Octree<D, [f32; 3]> {
+    /// Loads a point cloud, calling `payload` with the index of each point in the file and all of
+    /// the values stored for it (in the order of the file's columns or properties, such as
+    /// `x y z red green blue`) to get its item.
+    ///
+    /// # Errors
+    /// Returns a [`PointCloudError`] if reading fails or the file isn't in the given `format`.
+    pub fn load_points_with(
+        reader: impl Read,
+        format: PointFormat,
+        payload: impl FnMut(usize, &[f32]) -> D,
+    ) -> Result<Self, PointCloudError> {
+        let mut tree = Octree::new();
+        tree.add_points_with(reader, format, payload)?;
+        Ok(tree)
+    }
+
+    /// Adds the items of a point cloud to this tree like [`load_points_with`](Self::load_points_with),
+    /// returning the number of points read.
+    ///
+    /// This keeps the tree's settings, so a tree made with
+    /// [`with_bucket_size`](Self::with_bucket_size) stores the points in buckets.
+    ///
+    /// # Errors
+    /// Returns a [`PointCloudError`] if reading fails or the file isn't in the given `format`, in
+    /// which case the points before the error have already been added.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::{Octree, PointFormat};
+    ///
+    /// let file = "0 0 0\n0 0 1\n0 1 0\n";
+    /// let mut tree = Octree::with_bucket_size(4);
+    /// let read = tree.add_points_with(file.as_bytes(), PointFormat::Xyz, |index, _| index);
+    /// assert_eq!(read.unwrap(), 3);
+    /// assert_eq!(tree.stats().buckets, 1);
+    /// ```
+    pub fn add_points_with(
+        &mut self,
+        reader: impl Read,
+        format: PointFormat,
+        mut payload: impl FnMut(usize, &[f32]) -> D,
+    ) -> Result<usize, PointCloudError> {
+        let mut index = 0;
+        let add = |point: [f32; 3], values: &[f32]| {
+            self.add(&point, payload(index, values));
+            index += 1;
+        };
+        let reader = BufReader::new(reader);
+        match format {
+            PointFormat::Ply | PointFormat::BinaryPly => read_ply(reader, add)?,
+            PointFormat::Xyz => read_columns(reader, false, add)?,
+            PointFormat::Csv => read_columns(reader, true, add)?,
+        }
+        Ok(index)
+    }
+}
+
+impl<D, A> Octree<D, [f32; 3], A> {
+    /// Writes the point of every item in the tree (including [parked](Self::parked) ones) to
+    /// `writer` in the given `format`.
+    ///
+    /// # Errors
+    /// Returns any error from writing.
+    pub fn write_points(&self, writer: impl Write, format: PointFormat) -> io::Result<()> {
+        let mut points = Vec::with_capacity(self.len());
+        self.visit(|node| {
+            points.extend(node.points().map(|(point, _)| point));
+            VisitAction::Descend
+        });
+        points.extend(self.parked.iter().map(|(point, _)| point.to_array()));
+
+        let mut writer = BufWriter::new(writer);
+        match format {
+            PointFormat::Ply | PointFormat::BinaryPly => {
+                let encoding = if format == PointFormat::Ply {
+                    "ascii"
+                } else {
+                    "binary_little_endian"
+                };
+                write!(
+                    writer,
+                    "ply\nformat {encoding} 1.0\nelement vertex {}\n\
+                    property float x\nproperty float y\nproperty float z\nend_header\n",
+                    points.len()
+                )?;
+                for [x, y, z] in points {
+                    if format == PointFormat::Ply {
+                        writeln!(writer, "{x} {y} {z}")?;
+                    } else {
+                        for n in [x, y, z] {
+                            writer.write_all(&n.to_le_bytes())?;
+                        }
+                    }
+                }
+            }
+            PointFormat::Xyz => {
+                for [x, y, z] in points {
+                    writeln!(writer, "{x} {y} {z}")?;
+                }
+            }
+            PointFormat::Csv => {
+                writeln!(writer, "x,y,z")?;
+                for [x, y, z] in points {
+                    writeln!(writer, "{x},{y},{z}")?;
+                }
+            }
+        }
+        writer.flush()
+    }
+}
+
+/// Reads XYZ (or CSV) lines, calling `add` with each point and all of its values
+fn read_columns(
+    reader: impl BufRead,
+    csv: bool,
+    mut add: impl FnMut([f32; 3], &[f32]),
+) -> Result<(), PointCloudError> {
+    let mut axes = [0, 1, 2];
+    let mut first = true;
+    let mut values = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+        let fields: Vec<_> = if csv {
+            line.split(',')
+                .map(|field| field.trim().trim_matches('"'))
+                .collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+
+        // A CSV header names the columns
+        if std::mem::take(&mut first) && csv && fields.iter().any(|f| f.parse::<f32>().is_err()) {
+            for (axis, name) in axes.iter_mut().zip(["x", "y", "z"]) {
+                *axis = fields
+                    .iter()
+                    .position(|field| field.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| parse_error(i, format!("the header has no {name} column")))?;
+            }
+            continue;
+        }
+
+        values.clear();
+        for field in fields {
+            let value = field
+                .parse()
+                .map_err(|_| parse_error(i, format!("{field:?} isn't a number")))?;
+            values.push(value);
+        }
+        let point = axes.map(|axis| values.get(axis).copied());
+        let [Some(x), Some(y), Some(z)] = point else {
+            return Err(parse_error(
+                i,
+                "the line is missing coordinates".to_string(),
+            ));
+        };
+        add([x, y, z], &values);
+    }
+    Ok(())
+}
+
+fn parse_error(line_index: usize, message: String) -> PointCloudError {
+    PointCloudError::Parse {
+        line: line_index + 1,
+        message,
+    }
+}
+
+/// A PLY property type
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Reads a value from exactly `size` bytes
+    #[allow(clippy::cast_precision_loss)]
+    fn read(self, bytes: &[u8], big_endian: bool) -> f32 {
+        macro_rules! read {
+            ($ty:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                if big_endian {
+                    <$ty>::from_be_bytes(bytes)
+                } else {
+                    <$ty>::from_le_bytes(bytes)
+                }
+            }};
+        }
+        match self {
+            Scalar::I8 => f32::from(read!(i8)),
+            Scalar::U8 => f32::from(read!(u8)),
+            Scalar::I16 => f32::from(read!(i16)),
+            Scalar::U16 => f32::from(read!(u16)),
+            Scalar::I32 => read!(i32) as f32,
+            Scalar::U32 => read!(u32) as f32,
+            Scalar::F32 => read!(f32),
+            Scalar::F64 => read!(f64) as f32,
+        }
+    }
+}
+
+/// A PLY element (such as `vertex`), where `None` is a list property
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<(String, Option<Scalar>)>,
+}
+
+/// Reads a file line by line, keeping track of the line number for errors
+struct LineReader<R> {
+    reader: R,
+    line: String,
+    index: usize, // The index of the next line
+}
+
+impl<R: BufRead> LineReader<R> {
+    /// Reads the next line, returning its index
+    fn next(&mut self) -> Result<usize, PointCloudError> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Err(parse_error(
+                self.index,
+                "unexpected end of file".to_string(),
+            ));
+        }
+        self.index += 1;
+        Ok(self.index - 1)
+    }
+}
+
+/// Reads the vertices of a PLY file, calling `add` with each point and all of its properties
+fn read_ply(
+    reader: impl BufRead,
+    mut add: impl FnMut([f32; 3], &[f32]),
+) -> Result<(), PointCloudError> {
+    let mut reader = LineReader {
+        reader,
+        line: String::new(),
+        index: 0,
+    };
+    reader.next()?;
+    if reader.line.trim() != "ply" {
+        return Err(parse_error(0, "the file isn't a PLY file".to_string()));
+    }
+
+    let mut encoding = None; // Whether it is big endian if it is binary
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let i = reader.next()?;
+        let words: Vec<_> = reader.line.split_whitespace().collect();
+        match words[..] {
+            ["format", format, _] => {
+                encoding = Some(match format {
+                    "ascii" => None,
+                    "binary_little_endian" => Some(false),
+                    "binary_big_endian" => Some(true),
+                    _ => return Err(parse_error(i, format!("unknown format {format:?}"))),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| parse_error(i, format!("invalid element count {count:?}")))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", _, _, name] | ["property", _, name] => {
+                let scalar = Scalar::parse(words[1]);
+                if words[1] != "list" && scalar.is_none() {
+                    return Err(parse_error(i, format!("unknown type {:?}", words[1])));
+                }
+                elements
+                    .last_mut()
+                    .ok_or_else(|| parse_error(i, "property before any element".to_string()))?
+                    .properties
+                    .push((name.to_string(), scalar));
+            }
+            ["end_header"] => break,
+            ["comment" | "obj_info", ..] | [] => {}
+            _ => {
+                let line = reader.line.trim();
+                return Err(parse_error(i, format!("unknown header line {line:?}")));
+            }
+        }
+    }
+    let encoding = encoding
+        .ok_or_else(|| parse_error(reader.index, "the header has no format".to_string()))?;
+
+    for element in elements {
+        let scalars: Option<Vec<_>> = element.properties.iter().map(|(_, ty)| *ty).collect();
+        if element.name == "vertex" {
+            let Some(scalars) = scalars else {
+                return Err(PointCloudError::Unsupported(
+                    "list properties on vertices".to_string(),
+                ));
+            };
+            return read_vertices(&mut reader, &element, &scalars, encoding, &mut add);
+        }
+
+        // Skip anything before the vertices
+        match (encoding, scalars) {
+            (None, _) => {
+                for _ in 0..element.count {
+                    reader.next()?;
+                }
+            }
+            (Some(_), Some(scalars)) => {
+                let size = scalars.iter().map(|scalar| scalar.size()).sum::<usize>();
+                let len = (size * element.count) as u64;
+                if io::copy(&mut reader.reader.by_ref().take(len), &mut io::sink())? != len {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+            }
+            (Some(_), None) => {
+                return Err(PointCloudError::Unsupported(format!(
+                    "binary list properties before the vertices (in {:?})",
+                    element.name
+                )));
+            }
+        }
+    }
+    Err(parse_error(0, "the file has no vertex element".to_string()))
+}
+
+fn read_vertices(
+    reader: &mut LineReader<impl BufRead>,
+    element: &Element,
+    scalars: &[Scalar],
+    encoding: Option<bool>,
+    add: &mut impl FnMut([f32; 3], &[f32]),
+) -> Result<(), PointCloudError> {
+    let mut axes = [0; 3];
+    for (axis, name) in axes.iter_mut().zip(["x", "y", "z"]) {
+        *axis = element
+            .properties
+            .iter()
+            .position(|(property, _)| property == name)
+            .ok_or_else(|| parse_error(0, format!("the vertices have no {name} property")))?;
+    }
+
+    let mut bytes = vec![0; scalars.iter().map(|scalar| scalar.size()).sum()];
+    let mut values = Vec::with_capacity(scalars.len());
+    for _ in 0..element.count {
+        values.clear();
+        if let Some(big_endian) = encoding {
+            reader.reader.read_exact(&mut bytes)?;
+            let mut start = 0;
+            for scalar in scalars {
+                let end = start + scalar.size();
+                values.push(scalar.read(&bytes[start..end], big_endian));
+                start = end;
+            }
+        } else {
+            let i = reader.next()?;
+            for word in reader.line.split_whitespace().take(scalars.len()) {
+                let value = word
+                    .parse()
+                    .map_err(|_| parse_error(i, format!("{word:?} isn't a number")))?;
+                values.push(value);
+            }
+            if values.len() < scalars.len() {
+                return Err(parse_error(
+                    i,
+                    "the vertex is missing properties".to_string(),
+                ));
+            }
+        }
+        add(axes.map(|axis| values[axis]), &values);
+    }
+    Ok(())
+}
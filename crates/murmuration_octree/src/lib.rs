@@ -10,48 +10,131 @@
 //!
 //! This is designed to be reasonably memory efficient by skipping multiple layers of the tree where there is
 //! no branching, and will typically (provided the data `D` is fairly small) take around 80 bytes per
-//! stored item.
+//! stored item, or much less if items are grouped into buckets (see [`Octree::with_bucket_size`]).
 use nonmax::NonMaxU32;
+use path::Path;
+use remove::ParentBranch;
 use slab::Slab;
 use std::fmt::{Debug, Formatter};
 
 mod add;
+mod aggregate;
+mod binary;
+mod compact;
+mod compare;
+mod count;
+mod dump;
+mod error;
+mod extreme;
+mod frozen;
 mod get;
 mod impls;
+#[cfg(feature = "io")]
+mod io;
+mod morton;
+mod nearest;
+mod path;
+mod persistent;
 mod point;
+mod region;
 mod remove;
+mod saved;
+#[cfg(feature = "serde")]
+mod serialize;
+mod stats;
+mod validate;
+mod visit;
 mod within;
 
+pub use aggregate::Aggregate;
+pub use binary::BinaryPayload;
+pub use compare::OctreeDiff;
+#[cfg(feature = "io")]
+pub use error::PointCloudError;
+pub use error::{AddError, LoadError, ValidationError};
+pub use frozen::FrozenOctree;
+#[cfg(feature = "io")]
+pub use io::PointFormat;
+pub use persistent::PersistentOctree;
 pub use point::{ordered::OrderedBinary, Point, PointData};
+pub use saved::SavedOctree;
+pub use stats::OctreeStats;
+pub use visit::{NodeKind, NodeView, VisitAction};
 
 /// A 3D tree which stores items of type `D` so that they can be efficiently queried by location (`P`).
-pub struct Octree<D, P: Point> {
-    branches: Slab<Branch<D, P>>,
+///
+/// Every branch of the tree can also keep an [`Aggregate`] `A` of the items below it up to date, by
+/// default this is `()` which costs nothing.
+#[derive(Clone)]
+pub struct Octree<D, P: Point, A = ()> {
+    branches: Slab<Branch<D, P, A>>,
+    slab_end: usize, // One past the highest key ever used in branches (the length of its storage)
     root: Option<BranchKey>,
+    non_finite: NonFinitePolicy,
+    parked: Vec<(PointData<P>, D)>,
+    bucket_size: usize,
+    max_items: Option<usize>,
+}
+
+/// How an [`Octree`] handles points with a NaN or infinite coordinate when they are added.
+///
+/// Note that [`Octree::try_add`] will reject these points unless this is [`Park`](Self::Park).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NonFinitePolicy {
+    /// Add them to the tree as normal, where they will be ordered arbitrarily (beyond the
+    /// extremes of each axis) and may be returned by queries they shouldn't match.
+    #[default]
+    Insert,
+    /// Keep them in a side list (see [`Octree::parked`]) where they can still be moved or removed
+    /// but will never be returned by spatial queries.
+    Park,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "u32", try_from = "u32")
+)]
 pub(crate) struct BranchKey(NonMaxU32);
 
-enum Branch<D, P: Point> {
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "D: serde::Serialize",
+        deserialize = "D: serde::Deserialize<'de>, A: Default"
+    ))
+)]
+#[derive(Clone)]
+enum Branch<D, P: Point, A> {
     Split {
         children: [Option<BranchKey>; 8],
         occupied: u8, // Which children are Some (bitflags) (used for .remove).
         depth: u8,    // Equivalent to point_depth + 1 if there is a Skip above them
+        len: u32,     // The number of items below this branch
+        #[cfg_attr(feature = "serde", serde(skip))] // Recalculated when deserializing
+        aggregate: A, // Skip branches share the aggregate of their child
     },
     Skip {
         point: PointData<P>,
         point_depth: u8,
         child: BranchKey,
+        len: u32,
     },
     Leaf {
         point: PointData<P>,
         data: D,
         child: Option<BranchKey>,
     },
+    // Used instead of Leaf when the tree has a bucket size, the points can be anywhere in its cell
+    Bucket {
+        items: Vec<(PointData<P>, D)>,
+    },
 }
 
-impl<D, P: Point> Branch<D, P> {
+impl<D, P: Point, A> Branch<D, P, A> {
     fn new_data(point: PointData<P>, data: D) -> Self {
         Branch::Leaf {
             point,
@@ -59,34 +142,101 @@ impl<D, P: Point> Branch<D, P> {
             child: None,
         }
     }
+
+    /// Replaces the key of every child of this branch with `new_key(key)`, returning `None` if any
+    /// of them are `None`
+    fn map_keys(&mut self, mut new_key: impl FnMut(BranchKey) -> Option<BranchKey>) -> Option<()> {
+        match self {
+            Branch::Split { children, .. } => {
+                for child in children.iter_mut().flatten() {
+                    *child = new_key(*child)?;
+                }
+            }
+            Branch::Skip { child, .. }
+            | Branch::Leaf {
+                child: Some(child), ..
+            } => *child = new_key(*child)?,
+            Branch::Leaf { child: None, .. } | Branch::Bucket { .. } => {}
+        }
+        Some(())
+    }
 }
 
-impl<D, P: Point> Default for Octree<D, P> {
+impl<D, P: Point, A> Default for Octree<D, P, A> {
     fn default() -> Self {
         Self {
             branches: Slab::new(),
+            slab_end: 0,
             root: None,
+            non_finite: NonFinitePolicy::default(),
+            parked: Vec::new(),
+            bucket_size: 1,
+            max_items: None,
         }
     }
 }
 
 impl<D, P: Point> Octree<D, P> {
-    fn get_branch(&self, branch: BranchKey) -> &Branch<D, P> {
+    /// Returns a new empty `Octree`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a new empty `Octree` which stores up to `bucket_size` items together in each leaf
+    /// before splitting it, see [`set_bucket_size`](Self::set_bucket_size).
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::with_bucket_size(16);
+    /// for i in 0..100_u32 {
+    ///     tree.add(&[i, i * 2, i * 3], i);
+    /// }
+    /// assert_eq!(tree.within(&[10, 20, 30], 4).count(), 3);
+    /// ```
+    pub fn with_bucket_size(bucket_size: usize) -> Self {
+        Self {
+            bucket_size,
+            ..Self::default()
+        }
+    }
+
+    /// Returns a new empty `Octree` with room for about `items` items before it needs to reallocate,
+    /// see [`reserve`](Self::reserve).
+    pub fn with_capacity(items: usize) -> Self {
+        let mut tree = Self::default();
+        tree.reserve(items);
+        tree
+    }
+
+    /// Returns a new empty `Octree` which handles non-finite points according to `policy`.
+    pub fn with_non_finite_policy(policy: NonFinitePolicy) -> Self {
+        Self {
+            non_finite: policy,
+            ..Self::default()
+        }
+    }
+}
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    fn get_branch(&self, branch: BranchKey) -> &Branch<D, P, A> {
         let key: u32 = branch.0.into();
-        // SAFETY: It shouldn't be possible for the hierarchy to be incorrect
-        // unsafe { self.branches.get_unchecked(key as usize) } // TODO: Change back
-        self.branches.get(key as usize).unwrap()
+        debug_assert!(self.branches.contains(key as usize), "missing branch {key}");
+        // SAFETY: Every key in the hierarchy refers to a live branch (see `validate` and the differential tests)
+        unsafe { self.branches.get_unchecked(key as usize) }
     }
 
-    fn get_branch_mut(&mut self, branch: BranchKey) -> &mut Branch<D, P> {
+    fn get_branch_mut(&mut self, branch: BranchKey) -> &mut Branch<D, P, A> {
         let key: u32 = branch.0.into();
-        // SAFETY: It shouldn't be possible for the hierarchy to be incorrect
-        // unsafe { self.branches.get_unchecked_mut(key as usize) }
-        self.branches.get_mut(key as usize).unwrap()
+        debug_assert!(self.branches.contains(key as usize), "missing branch {key}");
+        // SAFETY: As for `get_branch`
+        unsafe { self.branches.get_unchecked_mut(key as usize) }
     }
 
-    fn add_branch(&mut self, branch: Branch<D, P>) -> BranchKey {
+    fn add_branch(&mut self, branch: Branch<D, P, A>) -> BranchKey {
         let key = self.branches.insert(branch);
+        self.slab_end = self.slab_end.max(key + 1);
         BranchKey(NonMaxU32::new(key.try_into().unwrap()).expect("Octree key overflowed 2^32-1"))
     }
 
@@ -95,18 +245,162 @@ impl<D, P: Point> Octree<D, P> {
         self.branches.remove(key as usize);
     }
 
-    /// Returns a new empty `Octree`.
-    pub fn new() -> Self {
-        Self::default()
+    /// Returns how this tree handles points with a NaN or infinite coordinate.
+    pub fn non_finite_policy(&self) -> NonFinitePolicy {
+        self.non_finite
+    }
+
+    /// Changes how this tree handles points with a NaN or infinite coordinate.
+    ///
+    /// This only affects items added after the change, anything already in the tree stays where it is.
+    pub fn set_non_finite_policy(&mut self, policy: NonFinitePolicy) {
+        self.non_finite = policy;
+    }
+
+    /// Returns the largest number of items stored together in a single leaf, where `1` means that
+    /// every point has its own leaf.
+    pub fn bucket_size(&self) -> usize {
+        self.bucket_size
+    }
+
+    /// Changes the number of items stored together in each leaf, where `0` or `1` means that every
+    /// point has its own leaf (the default).
+    ///
+    /// Buckets save a lot of memory (there is no branch per item) and make queries follow fewer
+    /// links between branches, at the cost of checking every item in a bucket whose cell is partly in
+    /// range. Items at exactly the same point are always kept together, so buckets can grow larger
+    /// than this if there are lots of duplicates.
+    ///
+    /// This only affects leaves created after the change, existing ones are split as they fill up.
+    pub fn set_bucket_size(&mut self, bucket_size: usize) {
+        self.bucket_size = bucket_size;
+    }
+
+    /// Returns the most items this tree will hold, if it has a limit.
+    pub fn max_items(&self) -> Option<usize> {
+        self.max_items
+    }
+
+    /// Limits the number of items (including parked ones) the tree will hold, after which
+    /// [`try_add`](Self::try_add) returns [`AddError::Full`] and [`add`](Self::add) panics.
+    ///
+    /// This doesn't remove anything if the tree already holds more than `max_items`.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::{AddError, Octree};
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.set_max_items(Some(2));
+    /// assert!(tree.try_add(&[1_u32, 2, 3], 'a').is_ok());
+    /// assert!(tree.try_add(&[4, 5, 6], 'b').is_ok());
+    /// assert_eq!(tree.try_add(&[7, 8, 9], 'c'), Err(AddError::Full('c')));
+    /// ```
+    pub fn set_max_items(&mut self, max_items: Option<usize>) {
+        self.max_items = max_items;
+    }
+
+    /// Reserves room for about `additional` more items in the tree's branch storage, so adding them
+    /// doesn't need to reallocate.
+    ///
+    /// Every item needs a leaf and usually a split above it (or one of each per bucket), so this is
+    /// only an estimate.
+    pub fn reserve(&mut self, additional: usize) {
+        let leaves = additional.div_ceil(self.bucket_size.max(1));
+        self.branches.reserve(leaves.saturating_mul(2));
+    }
+
+    /// Returns the reason there isn't room to add an item at `point`, if there isn't
+    fn no_room(&self, point: &PointData<P>) -> Option<fn(D) -> AddError<D>> {
+        if self.max_items.is_some_and(|max| self.len() >= max) {
+            return Some(AddError::Full);
+        }
+        if self.non_finite == NonFinitePolicy::Park && !point.is_finite() {
+            return None;
+        }
+        // A new item adds at most a leaf, split and skip, unless it overflows a bucket which is then
+        // split up into a tree of at most a bucket, split and skip per item
+        let bucket = if self.bucket_size > 1 {
+            self.bucket_size.min(self.len())
+        } else {
+            1
+        };
+        let branches = bucket.saturating_add(1).saturating_mul(3);
+        // The keys are less than `u32::MAX` and empty slots are reused before any new keys
+        if self.branches.len().saturating_add(branches) > u32::MAX as usize {
+            Some(AddError::OutOfKeys)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a new leaf (or bucket if the tree uses them) holding a single item
+    fn new_leaf(&self, point: PointData<P>, data: D) -> Branch<D, P, A> {
+        if self.bucket_size > 1 {
+            Branch::Bucket {
+                items: vec![(point, data)],
+            }
+        } else {
+            Branch::new_data(point, data)
+        }
+    }
+
+    /// Returns all the items which have been parked because their point wasn't finite.
+    ///
+    /// See [`NonFinitePolicy::Park`].
+    pub fn parked(&self) -> impl Iterator<Item = &D> {
+        self.parked.iter().map(|(_, data)| data)
+    }
+
+    /// Returns the number of items below the given branch.
+    fn branch_len(&self, branch: BranchKey) -> u32 {
+        let mut branch = Some(branch);
+        let mut len = 0;
+        while let Some(key) = branch {
+            match self.get_branch(key) {
+                Branch::Split { len: split_len, .. } | Branch::Skip { len: split_len, .. } => {
+                    return len + split_len;
+                }
+                Branch::Leaf { child, .. } => {
+                    len += 1;
+                    branch = *child;
+                }
+                Branch::Bucket { items } => return len + items.len() as u32,
+            }
+        }
+        len
+    }
+
+    /// Returns the number of items in the tree (including any [parked](Self::parked) ones).
+    pub fn len(&self) -> usize {
+        self.root.map_or(0, |root| self.branch_len(root) as usize) + self.parked.len()
+    }
+
+    /// Returns `true` if there are no items in the tree.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none() && self.parked.is_empty()
     }
 
     /// Returns the number of branches currently in the tree (will typically be around 2 * items)
     pub fn num_branches(&self) -> usize {
         self.branches.len()
     }
+
+    /// Returns the fraction (from `0.0` to `1.0`) of the tree's branch storage which is empty slots left
+    /// behind by removed branches.
+    ///
+    /// These slots are reused when adding items but may leave related branches scattered in memory.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fragmentation(&self) -> f32 {
+        if self.slab_end == 0 {
+            0.0
+        } else {
+            (self.slab_end - self.branches.len()) as f32 / self.slab_end as f32
+        }
+    }
 }
 
-impl<D: PartialEq, P: Point> Octree<D, P> {
+impl<D: PartialEq, P: Point, A: Aggregate<D, P>> Octree<D, P, A> {
     /// Move the given `data` from `old_point` to `new_point`, returning `true` if it existed at `old_point`.
     pub fn move_data(&mut self, old_point: &P, new_point: &P, data: D) -> bool {
         self.move_data_internal(&old_point.get_point(), new_point.get_point(), data)
@@ -119,58 +413,87 @@ impl<D: PartialEq, P: Point> Octree<D, P> {
         new_point: PointData<P>,
         data: D,
     ) -> bool {
-        if let Ok((leaf, parents)) = self.get_leaf_parents(old_point) {
-            if let Branch::Leaf {
-                child,
-                data: leaf_data,
-                ..
-            } = self.get_branch(leaf)
+        if !old_point.is_finite() && self.remove_parked(old_point, &data) {
+            self.add_int(new_point, data);
+            return true;
+        }
+
+        if let Some((leaf, parents)) = self.get_leaf_parents(old_point) {
+            // Optimise trivial moves within the same cell (unless the new point needs to be parked)
+            let parks = self.non_finite == NonFinitePolicy::Park && !new_point.is_finite();
+            if !parks
+                && (old_point ^ &new_point).leading_zeros() >= self.cell_depth(&parents)
+                && self.move_in_place(leaf, old_point, &new_point, &data)
             {
-                // Optimise trivial moves
-                if &data == leaf_data && child.is_none() {
-                    let trivial = if parents.is_empty() {
-                        true
-                    } else {
-                        let mut depth = 0;
-                        for parent in &parents {
-                            match self.get_branch(**parent) {
-                                Branch::Split { .. } => depth += 1,
-                                Branch::Skip { point_depth, .. } => {
-                                    depth += point_depth;
-                                    break;
-                                }
-                                Branch::Leaf { .. } => unreachable!(),
-                            }
-                        }
-                        let shared = (old_point ^ &new_point).leading_zeros();
-                        shared >= depth
-                    };
-
-                    if trivial {
-                        let Branch::Leaf { point, .. } = self.get_branch_mut(leaf) else {
-                            unreachable!()
-                        };
-                        *point = new_point;
-                        return true;
-                    }
-                }
+                self.refresh_aggregates(&new_point);
+                return true;
             }
 
-            if self.remove_from_parent_chain(leaf, parents, &data) {
+            if self.remove_from_parent_chain(leaf, &parents, old_point, &data) {
                 self.add_int(new_point, data);
                 return true;
             }
         }
         false
     }
+
+    /// Returns the number of leading bits shared by everything in the cell below `parents`
+    fn cell_depth(&self, parents: &Path<ParentBranch>) -> u8 {
+        let mut depth = 0;
+        for parent in parents.iter() {
+            match self.get_branch(**parent) {
+                Branch::Split { .. } => depth += 1,
+                Branch::Skip { point_depth, .. } => {
+                    depth += point_depth;
+                    break;
+                }
+                Branch::Leaf { .. } | Branch::Bucket { .. } => unreachable!(),
+            }
+        }
+        depth
+    }
+
+    /// Moves `data` to `new_point` without changing the structure of the tree if it is alone in its
+    /// leaf or in a bucket, returning `false` otherwise
+    fn move_in_place(
+        &mut self,
+        leaf: BranchKey,
+        old_point: &PointData<P>,
+        new_point: &PointData<P>,
+        data: &D,
+    ) -> bool {
+        match self.get_branch_mut(leaf) {
+            Branch::Leaf {
+                point,
+                data: leaf_data,
+                child: None,
+            } if leaf_data == data => {
+                *point = new_point.clone();
+                true
+            }
+            Branch::Bucket { items } => {
+                if let Some(item) = items
+                    .iter_mut()
+                    .find(|(point, item)| point == old_point && item == data)
+                {
+                    item.0 = new_point.clone();
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
 }
 
 // Manual impl to add the P::Data: Debug bound
-impl<D, P> Debug for Branch<D, P>
+impl<D, P, A> Debug for Branch<D, P, A>
 where
     D: Debug,
     P: Point + Debug,
     P::Data: Debug,
+    A: Debug,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -178,21 +501,27 @@ where
                 children,
                 occupied,
                 depth,
+                len,
+                aggregate,
             } => f
                 .debug_struct("Branch::Split")
                 .field("children", children)
                 .field("occupied", occupied)
                 .field("depth", depth)
+                .field("len", len)
+                .field("aggregate", aggregate)
                 .finish(),
             Branch::Skip {
                 point,
                 point_depth,
                 child,
+                len,
             } => f
                 .debug_struct("Branch::Skip")
                 .field("point", point)
                 .field("point_depth", point_depth)
                 .field("child", child)
+                .field("len", len)
                 .finish(),
             Branch::Leaf { point, data, child } => f
                 .debug_struct("Branch::Leaf")
@@ -200,29 +529,30 @@ where
                 .field("data", data)
                 .field("child", child)
                 .finish(),
+            Branch::Bucket { items } => f
+                .debug_struct("Branch::Bucket")
+                .field("items", items)
+                .finish(),
         }
     }
 }
 
-impl<D, P> Debug for Octree<D, P>
+impl<D, P, A> Debug for Octree<D, P, A>
 where
     D: Debug,
     P: Point + Debug,
     P::Data: Debug,
+    A: Debug,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let root: u32 = self.root.unwrap().0.into();
+        let root = self.root.map(|root| u32::from(root.0) as usize);
         write!(
             f,
             "Octree {{\n{}\n}}",
             self.branches
                 .iter()
                 .map(|(k, v)| {
-                    format!(
-                        "{:2}{}{v:?}",
-                        k,
-                        if k == root as usize { ">>" } else { "  " }
-                    )
+                    format!("{:2}{}{v:?}", k, if Some(k) == root { ">>" } else { "  " })
                 })
                 .collect::<Vec<_>>()
                 .join(",\n")
@@ -0,0 +1,192 @@
+use std::cmp::Reverse;
+use std::iter::FusedIterator;
+
+use super::{
+    path::Path,
+    point::{ordered::OrderedBinary, Point, PointData},
+    Branch, BranchKey, Octree,
+};
+
+struct MortonIter<'a, D, P: Point, A> {
+    octree: &'a Octree<D, P, A>,
+    parents: Path<(BranchKey, Option<u8>)>, // Splits also have the children left to look at
+    leaf: Option<BranchKey>,                // The next duplicate of the last leaf
+    bucket: Vec<&'a (PointData<P>, D)>,     // The rest of the current bucket, last first
+    cell: (PointData<P>, u8), // Only items sharing this many leading bits with the point are returned
+}
+
+impl<'a, D, P: Point, A> Iterator for MortonIter<'a, D, P, A> {
+    type Item = ([P::Data; 3], &'a D);
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(leaf) = self.leaf {
+            let Branch::Leaf { point, data, child } = self.octree.get_branch(leaf) else {
+                unreachable!()
+            };
+            self.leaf = *child;
+            return Some((point.to_array(), data));
+        }
+        if let Some((point, data)) = self.bucket.pop() {
+            return Some((point.to_array(), data));
+        }
+
+        loop {
+            let (branch, remaining) = self.parents.pop()?;
+            match self.octree.get_branch(branch) {
+                Branch::Split {
+                    children, occupied, ..
+                } => {
+                    let remaining = remaining.unwrap_or(*occupied);
+                    if remaining != 0 {
+                        let i = remaining.trailing_zeros() as usize;
+                        // SAFETY: `occupied` says which children exist (see `validate`)
+                        let child = unsafe { children[i].unwrap_unchecked() };
+                        self.parents
+                            .push((branch, Some(remaining & (remaining - 1))));
+                        self.parents.push((child, None));
+                    }
+                }
+                Branch::Skip { child, .. } => self.parents.push((*child, None)),
+                Branch::Leaf { point, data, child } => {
+                    self.leaf = *child;
+                    return Some((point.to_array(), data));
+                }
+                Branch::Bucket { items } => {
+                    // A bucket's cell can be bigger than the one being iterated over
+                    let (cell, depth) = &self.cell;
+                    self.bucket.extend(
+                        items
+                            .iter()
+                            .filter(|(point, _)| (point ^ cell).leading_zeros() >= *depth),
+                    );
+                    // Buckets aren't kept in order, and are small enough to sort as they are reached
+                    self.bucket
+                        .sort_unstable_by_key(|(point, _)| Reverse(point.morton()));
+                    if let Some((point, data)) = self.bucket.pop() {
+                        return Some((point.to_array(), data));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<D, P: Point, A> FusedIterator for MortonIter<'_, D, P, A> {}
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Returns all the items (not including [parked](Self::parked) ones) and their points, in
+    /// [Morton order](https://en.wikipedia.org/wiki/Z-order_curve) so that items close together
+    /// in space are mostly close together in the order (see [`PointData::morton`]).
+    ///
+    /// Items at the same point are returned in an unspecified order.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[3_u8, 3, 3], "far");
+    /// tree.add(&[1, 0, 0], "x");
+    /// tree.add(&[0, 0, 1], "z");
+    /// let order = tree.iter_morton().map(|(_, name)| *name).collect::<Vec<_>>();
+    /// assert_eq!(order, ["z", "x", "far"]);
+    /// ```
+    pub fn iter_morton(&self) -> impl Iterator<Item = ([P::Data; 3], &D)> {
+        self.iter_branch(self.root, PointData::ZERO, 0)
+    }
+
+    /// Returns all the items (not including [parked](Self::parked) ones) and their points in the
+    /// cell of the tree at `depth` with the coordinates `cell`, in Morton order.
+    ///
+    /// The cells at `depth` split each axis into `2^depth` equal ranges of ordered coordinates (see
+    /// [`OrderedBinary`]), so a point is in the cell given by
+    /// [`PointData::cell_coords`] and cells are only evenly sized in space for integer coordinates.
+    /// Only the lowest `depth` bits of each of `cell` are used.
+    ///
+    /// This only has to walk down to the cell to find its items, so it is much cheaper than
+    /// querying a box.
+    ///
+    /// # Panics
+    /// If `depth` is more than [`Point::MAX_DEPTH`].
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::{Octree, Point};
+    ///
+    /// let mut tree = Octree::new();
+    /// for (i, x) in [10_u32, 20, 300, 1000].into_iter().enumerate() {
+    ///     tree.add(&[x, 5, 5], i);
+    /// }
+    /// // The 256 wide chunk each point is in
+    /// let depth = 32 - 8;
+    /// let chunk = [20_u32, 5, 5].get_point().cell_coords(depth);
+    /// assert_eq!(chunk, [0, 0, 0]);
+    /// let found = tree.in_cell(depth, chunk).map(|(_, i)| *i).collect::<Vec<_>>();
+    /// assert_eq!(found, [0, 1]);
+    /// assert_eq!(tree.in_cell(depth, [1, 0, 0]).count(), 1);
+    /// ```
+    pub fn in_cell(
+        &self,
+        depth: u8,
+        cell: [<P::Data as OrderedBinary>::Ordered; 3],
+    ) -> impl Iterator<Item = ([P::Data; 3], &D)> {
+        assert!(depth <= P::MAX_DEPTH, "depth {depth} is too deep");
+        let prefix = if depth == 0 {
+            PointData::ZERO
+        } else {
+            PointData(cell.map(|n| n << (P::MAX_DEPTH - depth)))
+        };
+        self.iter_branch(self.find_cell(&prefix, depth), prefix, depth)
+    }
+
+    /// Returns the highest branch whose items all share the first `depth` bits with `prefix` (or a
+    /// bucket which some of them may be in)
+    fn find_cell(&self, prefix: &PointData<P>, depth: u8) -> Option<BranchKey> {
+        let mut branch = self.root?;
+        let mut branch_depth = 0; // The number of bits shared by everything below the branch
+        while branch_depth < depth {
+            match self.get_branch(branch) {
+                Branch::Leaf { point, .. } => {
+                    return ((point ^ prefix).leading_zeros() >= depth).then_some(branch);
+                }
+                Branch::Bucket { .. } => return Some(branch),
+                Branch::Skip {
+                    point,
+                    point_depth,
+                    child,
+                    ..
+                } => {
+                    if (point ^ prefix).leading_zeros() < depth.min(*point_depth) {
+                        return None;
+                    }
+                    branch = *child;
+                    branch_depth = *point_depth;
+                }
+                Branch::Split { children, .. } => {
+                    branch = children[prefix.nth(branch_depth) as usize]?;
+                    branch_depth += 1;
+                }
+            }
+        }
+        Some(branch)
+    }
+
+    /// Iterates over the items below `branch` which share the first `depth` bits with `cell`
+    fn iter_branch(
+        &self,
+        branch: Option<BranchKey>,
+        cell: PointData<P>,
+        depth: u8,
+    ) -> MortonIter<'_, D, P, A> {
+        let mut parents = Path::new();
+        if let Some(branch) = branch {
+            parents.push((branch, None));
+        }
+        MortonIter {
+            octree: self,
+            parents,
+            leaf: None,
+            bucket: Vec::new(),
+            cell: (cell, depth),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::iter::FusedIterator;
+
+use super::{
+    point::{ordered::OrderedBinary, Point, PointData},
+    region::distance_bounds,
+    visit::{NodeKind, NodeView},
+    Octree,
+};
+
+type Ordered<P> = <<P as Point>::Data as OrderedBinary>::Ordered;
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Returns the item closest to `point` (or one of them if there is a tie), or `None` if the tree
+    /// is empty.
+    ///
+    /// Items whose distance is NaN and [parked](Self::parked) items are never returned.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1.0_f32, 2.0, 3.0], "a");
+    /// tree.add(&[10.0, 2.0, 3.0], "b");
+    /// assert_eq!(tree.nearest(&[7.0, 0.0, 0.0]), Some(&"b"));
+    /// ```
+    pub fn nearest(&self, point: &P) -> Option<&D> {
+        self.nearest_iter(point).next()
+    }
+
+    /// Returns every item (not including [parked](Self::parked) ones or any whose distance is NaN) in
+    /// order of distance from `point`, closest first, so `nearest_iter(point).take(k)` gives the `k`
+    /// nearest items.
+    ///
+    /// This only looks at as much of the tree as it needs to, so taking a few items is cheap.
+    pub fn nearest_iter(&self, point: &P) -> impl Iterator<Item = &D> {
+        let centre = point.get_point();
+        let mut heap = BinaryHeap::new();
+        if let Some(root) = self.root_node() {
+            heap.push(Candidate {
+                distance: Ordered::<P>::from(0),
+                entry: Entry::Node(root),
+            });
+        }
+        Nearest { centre, heap }
+    }
+
+    /// Returns an item at most `1 + epsilon` times as far from `point` as the closest item, or
+    /// `None` if the tree is empty.
+    ///
+    /// This stops searching as soon as nothing left in the tree could be close enough to beat the
+    /// best item found so far by more than that, so it looks at much less of a large tree than
+    /// [`nearest`](Self::nearest) (which is the same as an `epsilon` of `0`). A negative `epsilon` is
+    /// treated as `0`.
+    ///
+    /// Items whose distance is NaN and [parked](Self::parked) items are never returned.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[0.0_f32, 0.0, 0.0], "a");
+    /// tree.add(&[10.0, 0.0, 0.0], "b");
+    /// // Both are within 1.5 times the distance to the closest (4)
+    /// let found = tree.nearest_approx(&[4.0, 0.0, 0.0], 0.5).unwrap();
+    /// assert!(found == &"a" || found == &"b");
+    /// assert_eq!(tree.nearest_approx(&[4.0, 0.0, 0.0], 0.1), Some(&"a"));
+    /// ```
+    pub fn nearest_approx(&self, point: &P, epsilon: P::Data) -> Option<&D> {
+        let centre = point.get_point();
+        let epsilon = if epsilon > P::Data::ZERO {
+            epsilon
+        } else {
+            P::Data::ZERO
+        };
+        // Squared distances are scaled by (1 + epsilon)^2 = 1 + (epsilon^2 + 2 * epsilon)
+        let factor = epsilon
+            .scale_distance(&epsilon)
+            .add_distances(&epsilon)
+            .add_distances(&epsilon);
+
+        let mut best: Option<(P::Data, &D)> = None;
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate {
+            distance: Ordered::<P>::from(0),
+            entry: Entry::Node(self.root_node()?),
+        });
+        while let Some(candidate) = heap.pop() {
+            let Entry::Node(node) = candidate.entry else {
+                unreachable!("only nodes are pushed")
+            };
+            if let Some((best_distance, _)) = &best {
+                let closest = P::Data::from_ordered(candidate.distance);
+                // Everything left is at least as far away as this node
+                if *best_distance <= closest.add_distances(&closest.scale_distance(&factor)) {
+                    break;
+                }
+            }
+            if matches!(node.kind(), NodeKind::Leaf | NodeKind::Bucket) {
+                for (point, data) in node.point_data() {
+                    // A NaN distance doesn't compare with anything so it would never be replaced
+                    let distance = point.distance_squared(&centre);
+                    if distance.is_irrelevant() {
+                        continue;
+                    }
+                    let closer = match &best {
+                        Some((best, _)) => distance < *best,
+                        None => true,
+                    };
+                    if closer {
+                        best = Some((distance, data));
+                    }
+                }
+            } else {
+                for child in node.children() {
+                    let (min, max) = child.cell_data();
+                    let (closest, _) = distance_bounds(&centre, &min, &max);
+                    heap.push(Candidate {
+                        distance: closest.to_ordered(),
+                        entry: Entry::Node(child),
+                    });
+                }
+            }
+        }
+        best.map(|(_, data)| data)
+    }
+}
+
+/// Something in the tree which could be the next nearest item
+pub(crate) struct Candidate<'a, D, P: Point, A> {
+    pub(crate) distance: Ordered<P>, // A lower bound of the squared distance for nodes
+    pub(crate) entry: Entry<'a, D, P, A>,
+}
+
+pub(crate) enum Entry<'a, D, P: Point, A> {
+    Node(NodeView<'a, D, P, A>),
+    Item(&'a PointData<P>, &'a D),
+}
+
+// Distances are never negative so their ordered forms can be compared directly
+impl<D, P: Point, A> Ord for Candidate<'_, D, P, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so that the heap pops the closest first
+        other.distance.cmp(&self.distance)
+    }
+}
+
+impl<D, P: Point, A> PartialOrd for Candidate<'_, D, P, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<D, P: Point, A> PartialEq for Candidate<'_, D, P, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl<D, P: Point, A> Eq for Candidate<'_, D, P, A> {}
+
+struct Nearest<'a, D, P: Point, A> {
+    centre: PointData<P>,
+    heap: BinaryHeap<Candidate<'a, D, P, A>>,
+}
+
+impl<'a, D, P: Point, A> Iterator for Nearest<'a, D, P, A> {
+    type Item = &'a D;
+    fn next(&mut self) -> Option<&'a D> {
+        loop {
+            let node = match self.heap.pop()?.entry {
+                Entry::Item(_, data) => return Some(data),
+                Entry::Node(node) => node,
+            };
+            if matches!(node.kind(), NodeKind::Leaf | NodeKind::Bucket) {
+                for (point, data) in node.point_data() {
+                    // NaN distances could be ordered ahead of real ones, so they are left out
+                    let distance = point.distance_squared(&self.centre);
+                    if !distance.is_irrelevant() {
+                        self.heap.push(Candidate {
+                            distance: distance.to_ordered(),
+                            entry: Entry::Item(point, data),
+                        });
+                    }
+                }
+            } else {
+                for child in node.children() {
+                    let (min, max) = child.cell_data();
+                    let (closest, _) = distance_bounds(&self.centre, &min, &max);
+                    self.heap.push(Candidate {
+                        distance: closest.to_ordered(),
+                        entry: Entry::Node(child),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<D, P: Point, A> FusedIterator for Nearest<'_, D, P, A> {}
@@ -0,0 +1,41 @@
+/// The most branches there can be on the way from the root down to an item, including its leaf.
+///
+/// Every split or skip branch fixes at least one more leading bit of its cell, so there are at most
+/// `P::MAX_DEPTH` of them above a leaf, and the widest coordinates (`u128`) have 128 bits.
+const MAX_PATH: usize = 128 + 1;
+
+/// A fixed capacity stack of the branches on the way down the tree, so walking it doesn't allocate
+pub(crate) struct Path<T: Copy> {
+    items: [Option<T>; MAX_PATH],
+    len: usize,
+}
+
+impl<T: Copy> Path<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            items: [None; MAX_PATH],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, item: T) {
+        debug_assert!(self.len < MAX_PATH, "the tree is deeper than its points");
+        self.items[self.len] = Some(item);
+        self.len += 1;
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        self.items[self.len].take()
+    }
+
+    /// Returns the most recently pushed item
+    pub(crate) fn top(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    /// Iterates from the top of the stack (the deepest branch) up to the root
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items[..self.len].iter().rev().flatten()
+    }
+}
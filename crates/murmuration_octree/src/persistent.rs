@@ -0,0 +1,365 @@
+use std::iter::FusedIterator;
+use std::sync::Arc;
+
+use super::{
+    point::{ordered::OrderedBinary, Point, PointData},
+    region::{Aabb, Overlap, Region, Sphere},
+};
+
+/// An octree whose nodes are shared between copies, so [`snapshot`](Self::snapshot) is O(1) and old
+/// snapshots can still be queried while the tree keeps changing.
+///
+/// Each change only copies the nodes on the way to the changed item, and only if a snapshot is still
+/// sharing them, so keeping a snapshot every tick (for example to query positions as they were several
+/// ticks ago for rollback) costs memory in proportion to how much has moved rather than the size of
+/// the tree.
+///
+/// Unlike [`Octree`](crate::Octree) this has no buckets, aggregates or parking, points with a NaN or
+/// infinite coordinate are added like any others (as with
+/// [`NonFinitePolicy::Insert`](crate::NonFinitePolicy::Insert)).
+///
+/// # Example
+/// ```
+/// use murmuration_octree::PersistentOctree;
+///
+/// let mut tree = PersistentOctree::new();
+/// tree.add(&[1.0_f32, 2.0, 3.0], "player");
+/// let before = tree.snapshot();
+///
+/// tree.move_data(&[1.0, 2.0, 3.0], &[50.0, 2.0, 3.0], "player");
+/// assert_eq!(before.get_single(&[1.0, 2.0, 3.0]), Some(&"player"));
+/// assert_eq!(tree.get_single(&[1.0, 2.0, 3.0]), None);
+/// ```
+pub struct PersistentOctree<D, P: Point> {
+    root: Option<Arc<Node<D, P>>>,
+}
+
+#[derive(Clone)]
+enum Node<D, P: Point> {
+    Split {
+        point: PointData<P>, // Any point below this, only the first `depth` bits are meaningful
+        depth: u8,           // The number of leading bits shared by everything below this
+        children: [Option<Arc<Node<D, P>>>; 8],
+        len: usize,
+    },
+    Leaf {
+        point: PointData<P>,
+        items: Vec<D>, // Every item at exactly this point, never empty
+    },
+}
+
+impl<D, P: Point> Node<D, P> {
+    fn new_leaf(point: PointData<P>, data: D) -> Arc<Self> {
+        Arc::new(Node::Leaf {
+            point,
+            items: vec![data],
+        })
+    }
+
+    fn point(&self) -> &PointData<P> {
+        match self {
+            Node::Split { point, .. } | Node::Leaf { point, .. } => point,
+        }
+    }
+
+    fn depth(&self) -> u8 {
+        match self {
+            Node::Split { depth, .. } => *depth,
+            Node::Leaf { .. } => P::MAX_DEPTH,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Node::Split { len, .. } => *len,
+            Node::Leaf { items, .. } => items.len(),
+        }
+    }
+}
+
+impl<D, P: Point> Clone for PersistentOctree<D, P> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<D, P: Point> Default for PersistentOctree<D, P> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+impl<D, P: Point> PersistentOctree<D, P> {
+    /// Returns a new empty `PersistentOctree`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the tree as it is now in O(1), which won't be affected by any later changes
+    /// to this tree (or this tree by changes to it).
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// Returns `true` if `self` and `other` are the same version of the tree, so one is an unchanged
+    /// snapshot of the other. This doesn't compare their items so can return `false` for equal trees.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(root), Some(other)) => Arc::ptr_eq(root, other),
+            (root, other) => root.is_none() && other.is_none(),
+        }
+    }
+
+    /// Returns the number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.len())
+    }
+
+    /// Returns `true` if there are no items in the tree.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns all the items at the given `point`.
+    pub fn get(&self, point: &P) -> impl Iterator<Item = &D> {
+        self.get_items(&point.get_point()).iter()
+    }
+
+    /// Returns one of the items at the given `point` or `None` if there aren't any.
+    pub fn get_single(&self, point: &P) -> Option<&D> {
+        self.get(point).next()
+    }
+
+    fn get_items(&self, point: &PointData<P>) -> &[D] {
+        let Some(mut node) = self.root.as_ref() else {
+            return &[];
+        };
+        loop {
+            if (point ^ node.point()).leading_zeros() < node.depth() {
+                return &[];
+            }
+            match &**node {
+                Node::Leaf { items, .. } => return items,
+                Node::Split {
+                    children, depth, ..
+                } => match &children[point.nth(*depth) as usize] {
+                    Some(child) => node = child,
+                    None => return &[],
+                },
+            }
+        }
+    }
+
+    /// Returns every item in the tree, in an unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = &D> {
+        self.query(Everything)
+    }
+
+    /// Returns all items within `distance` of `point`, in an unspecified order.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn within(&self, point: &P, distance: P::Data) -> impl Iterator<Item = &D> {
+        self.query(Sphere {
+            centre: point.get_point(),
+            sqr_dist: distance.distance_squared(&P::Data::ZERO),
+        })
+    }
+
+    /// Returns all items between `min` and `max` (inclusive) on every axis, in an unspecified order.
+    pub fn in_aabb(&self, min: &P, max: &P) -> impl Iterator<Item = &D> {
+        self.query(Aabb {
+            min: min.get_point(),
+            max: max.get_point(),
+        })
+    }
+
+    /// Returns the number of items within `distance` of `point`.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn count_within(&self, point: &P, distance: P::Data) -> usize {
+        let region = Sphere {
+            centre: point.get_point(),
+            sqr_dist: distance.distance_squared(&P::Data::ZERO),
+        };
+        let mut count = 0;
+        let mut stack = self.root.iter().collect::<Vec<_>>();
+        while let Some(node) = stack.pop() {
+            let (min, max) = node.point().cell_bounds(node.depth());
+            match (region.overlap(&min, &max), &**node) {
+                (Overlap::Outside, _) => {}
+                (Overlap::Inside, _) => count += node.len(),
+                (Overlap::Partial, Node::Leaf { point, items }) => {
+                    if region.contains(point) {
+                        count += items.len();
+                    }
+                }
+                (Overlap::Partial, Node::Split { children, .. }) => {
+                    stack.extend(children.iter().flatten());
+                }
+            }
+        }
+        count
+    }
+
+    fn query<R: Region<P>>(&self, region: R) -> PersistentIter<'_, D, P, R> {
+        PersistentIter {
+            region,
+            stack: self.root.iter().map(|root| (&**root, false)).collect(),
+            items: [].iter(),
+        }
+    }
+}
+
+impl<D: Clone, P: Point> PersistentOctree<D, P> {
+    /// Adds an item to the tree at the given point with `data`.
+    ///
+    /// This copies the nodes on the way to `point` if they are shared with a snapshot, which is why
+    /// `D` has to be `Clone`.
+    pub fn add(&mut self, point: &P, data: D) {
+        let point = point.get_point();
+        match &mut self.root {
+            Some(root) => Self::add_to_node(root, point, data),
+            None => self.root = Some(Node::new_leaf(point, data)),
+        }
+    }
+
+    fn add_to_node(node: &mut Arc<Node<D, P>>, point: PointData<P>, data: D) {
+        let shared = (&point ^ node.point()).leading_zeros();
+        if shared < node.depth() {
+            // The point is outside this node's cell, so they are split where they first differ
+            let mut children: [Option<Arc<Node<D, P>>>; 8] = Default::default();
+            children[node.point().nth(shared) as usize] = Some(Arc::clone(node));
+            children[point.nth(shared) as usize] = Some(Node::new_leaf(point.clone(), data));
+            *node = Arc::new(Node::Split {
+                point,
+                depth: shared,
+                children,
+                len: node.len() + 1,
+            });
+            return;
+        }
+
+        match Arc::make_mut(node) {
+            Node::Leaf { items, .. } => items.push(data),
+            Node::Split {
+                children,
+                depth,
+                len,
+                ..
+            } => {
+                *len += 1;
+                match &mut children[point.nth(*depth) as usize] {
+                    Some(child) => Self::add_to_node(child, point, data),
+                    child @ None => *child = Some(Node::new_leaf(point, data)),
+                }
+            }
+        }
+    }
+}
+
+impl<D: Clone + PartialEq, P: Point> PersistentOctree<D, P> {
+    /// Removes the given `data` from `point` in the tree if it exists, otherwise returns `false`.
+    pub fn remove(&mut self, point: &P, data: &D) -> bool {
+        let point = point.get_point();
+        // Checked first so that nothing is copied if it isn't there
+        if !self.get_items(&point).contains(data) {
+            return false;
+        }
+        Self::remove_from_node(&mut self.root, &point, data);
+        true
+    }
+
+    /// Move the given `data` from `old_point` to `new_point`, returning `true` if it existed at
+    /// `old_point`.
+    pub fn move_data(&mut self, old_point: &P, new_point: &P, data: D) -> bool {
+        if self.remove(old_point, &data) {
+            self.add(new_point, data);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes `data` from below `slot`, where it must be at `point`
+    fn remove_from_node(slot: &mut Option<Arc<Node<D, P>>>, point: &PointData<P>, data: &D) {
+        let Some(node) = slot else {
+            return;
+        };
+        // Empty leaves are removed and splits with only one child left are replaced by it
+        let replacement = match Arc::make_mut(node) {
+            Node::Leaf { items, .. } => {
+                if let Some(ind) = items.iter().position(|item| item == data) {
+                    items.remove(ind);
+                }
+                items.is_empty().then_some(None)
+            }
+            Node::Split {
+                children,
+                depth,
+                len,
+                ..
+            } => {
+                *len -= 1;
+                Self::remove_from_node(&mut children[point.nth(*depth) as usize], point, data);
+                (children.iter().flatten().count() == 1)
+                    .then(|| children.iter_mut().find_map(Option::take))
+            }
+        };
+        if let Some(replacement) = replacement {
+            *slot = replacement;
+        }
+    }
+}
+
+/// The region containing every point
+struct Everything;
+
+impl<P: Point> Region<P> for Everything {
+    fn overlap(&self, _min: &PointData<P>, _max: &PointData<P>) -> Overlap {
+        Overlap::Inside
+    }
+
+    fn contains(&self, _point: &PointData<P>) -> bool {
+        true
+    }
+}
+
+struct PersistentIter<'a, D, P: Point, R> {
+    region: R,
+    stack: Vec<(&'a Node<D, P>, bool)>, // Nodes to look at and whether they are inside the region
+    items: std::slice::Iter<'a, D>,
+}
+
+impl<'a, D, P: Point, R: Region<P>> Iterator for PersistentIter<'a, D, P, R> {
+    type Item = &'a D;
+    fn next(&mut self) -> Option<&'a D> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(item);
+            }
+
+            let (node, inside) = self.stack.pop()?;
+            let overlap = if inside {
+                Overlap::Inside
+            } else {
+                let (min, max) = node.point().cell_bounds(node.depth());
+                self.region.overlap(&min, &max)
+            };
+            match (overlap, node) {
+                (Overlap::Outside, _) => {}
+                (_, Node::Leaf { point, items }) => {
+                    if overlap == Overlap::Inside || self.region.contains(point) {
+                        self.items = items.iter();
+                    }
+                }
+                (_, Node::Split { children, .. }) => {
+                    let inside = overlap == Overlap::Inside;
+                    self.stack
+                        .extend(children.iter().flatten().map(|child| (&**child, inside)));
+                }
+            }
+        }
+    }
+}
+
+impl<D, P: Point, R: Region<P>> FusedIterator for PersistentIter<'_, D, P, R> {}
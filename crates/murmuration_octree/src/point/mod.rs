@@ -1,7 +1,6 @@
 pub mod ordered;
 pub mod unsigned;
 
-use std::cmp::Ordering;
 use std::fmt::{Debug, Formatter};
 use std::ops::BitXor;
 
@@ -10,6 +9,12 @@ use unsigned::Unsigned;
 
 /// The underlying ordered type used for positioning in the [`Octree`](crate::Octree).
 #[derive(Clone, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = ""),
+    allow(clippy::unsafe_derive_deserialize) // The unsafe code doesn't rely on the values
+)]
 pub struct PointData<P: Point>(pub [<P::Data as OrderedBinary>::Ordered; 3]);
 
 impl<P: Point> PartialEq for PointData<P> {
@@ -27,6 +32,77 @@ impl<P: Point> Debug for PointData<P> {
 }
 
 impl<P: Point> PointData<P> {
+    /// Returns `true` if none of the coordinates are NaN or infinite.
+    pub fn is_finite(&self) -> bool {
+        self.0.iter().all(|n| P::Data::from_ordered(*n).is_finite())
+    }
+
+    /// Converts this back into the coordinates it was created from.
+    pub fn to_array(&self) -> [P::Data; 3] {
+        self.0.map(P::Data::from_ordered)
+    }
+
+    /// Returns the coordinates of the cell of the tree at `depth` this point is in, which is the
+    /// first `depth` bits of each ordered coordinate, see [`Octree::in_cell`](crate::Octree::in_cell).
+    ///
+    /// # Panics
+    /// If `depth` is more than [`Point::MAX_DEPTH`].
+    pub fn cell_coords(&self, depth: u8) -> [<P::Data as OrderedBinary>::Ordered; 3] {
+        assert!(depth <= P::MAX_DEPTH, "depth {depth} is too deep");
+        if depth == 0 {
+            [<<P::Data as OrderedBinary>::Ordered as Unsigned>::ZERO; 3]
+        } else {
+            self.0.map(|n| n >> (P::MAX_DEPTH - depth))
+        }
+    }
+
+    /// Returns the position of this point along the
+    /// [Z-order curve](https://en.wikipedia.org/wiki/Z-order_curve), which is the order
+    /// [`Octree::iter_morton`](crate::Octree::iter_morton) returns items in.
+    ///
+    /// This is the bits of the ordered coordinates interleaved (starting with the highest bit of x,
+    /// then y, then z), split into three numbers with the most significant first, so comparing two
+    /// codes compares the points' positions along the curve. It can be turned back into the point
+    /// with [`from_morton`](Self::from_morton).
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::{Point, PointData};
+    ///
+    /// let point = [0b1000_u8, 0b0100, 0b1100].get_point();
+    /// assert_eq!(point.morton(), [0, 0b1010, 0b1100_0000]);
+    /// assert_eq!(PointData::from_morton(point.morton()), point);
+    /// ```
+    pub fn morton(&self) -> [<P::Data as OrderedBinary>::Ordered; 3] {
+        let bits = usize::from(P::MAX_DEPTH);
+        let mut code = [<<P::Data as OrderedBinary>::Ordered as Unsigned>::ZERO; 3];
+        for depth in 0..P::MAX_DEPTH {
+            let shift = P::MAX_DEPTH - 1 - depth;
+            for axis in 0..3 {
+                let bit = self.0[axis] >> shift & 1.into();
+                let ind = usize::from(depth) * 3 + axis;
+                code[ind / bits] = code[ind / bits] | bit << (bits - 1 - ind % bits) as u8;
+            }
+        }
+        code
+    }
+
+    /// Returns the point with the given position along the Z-order curve, see
+    /// [`morton`](Self::morton).
+    pub fn from_morton(code: [<P::Data as OrderedBinary>::Ordered; 3]) -> Self {
+        let bits = usize::from(P::MAX_DEPTH);
+        let mut point = Self::ZERO;
+        for depth in 0..P::MAX_DEPTH {
+            let shift = P::MAX_DEPTH - 1 - depth;
+            for axis in 0..3 {
+                let ind = usize::from(depth) * 3 + axis;
+                let bit = code[ind / bits] >> (bits - 1 - ind % bits) as u8 & 1.into();
+                point.0[axis] = point.0[axis] | bit << shift;
+            }
+        }
+        point
+    }
+
     pub(crate) const ZERO: Self =
         Self([<<P::Data as OrderedBinary>::Ordered as OrderedBinary>::ZERO; 3]);
 
@@ -58,60 +134,36 @@ impl<P: Point> PointData<P> {
             P::Data::from_ordered(self.0[2]),
             P::Data::from_ordered(other.0[2]),
         );
-        self_x.distance_squared(&other_x)
-            + self_y.distance_squared(&other_y)
-            + self_z.distance_squared(&other_z)
+        self_x
+            .distance_squared(&other_x)
+            .add_distances(&self_y.distance_squared(&other_y))
+            .add_distances(&self_z.distance_squared(&other_z))
     }
 
-    /// Returns the squared distance between self and centre (if we go down the ind branch) as a best case at this depth
-    pub(crate) fn closest_distance(&self, ind: u8, centre: &Self, depth: u8) -> P::Data {
-        // We use self from above depth - 1, ind at depth, and then compare with centre
+    /// Combine an index from .nth with self at the given depth
+    pub(crate) fn combine_ind(&self, ind: u8, depth: u8) -> Self {
         let shift = P::MAX_DEPTH - depth;
-        let increase_mask = <P::Data as OrderedBinary>::Ordered::MAX >> depth;
-
-        let mut dist = P::Data::ZERO;
-        for i in 0..=2 {
-            let ind_part: <P::Data as OrderedBinary>::Ordered = (ind & (4 >> i)).into();
-            let child = if shift >= 31 {
-                0.into()
-            } else {
-                self.0[i] >> (shift + 1) << (shift + 1)
-            } | (ind_part >> (2 - i as u8) << shift);
-            let centre_data = P::Data::from_ordered(centre.0[i]);
-            let centre = centre.0[i] >> shift << shift;
-            dist = dist
-                + match child.cmp(&centre) {
-                    Ordering::Equal => P::Data::ZERO,
-                    Ordering::Less => {
-                        centre_data.distance_squared(&P::Data::from_ordered(child | increase_mask))
-                    }
-                    Ordering::Greater => {
-                        centre_data.distance_squared(&P::Data::from_ordered(child))
-                    }
-                };
-        }
-        dist
+        let (min, _) = self.cell_bounds(depth - 1);
+        let ind_part = |n: u8| <P::Data as OrderedBinary>::Ordered::from(ind >> n & 1) << shift;
+        PointData([
+            min.0[0] | ind_part(2),
+            min.0[1] | ind_part(1),
+            min.0[2] | ind_part(0),
+        ])
     }
 
-    /// Combine an index from .nth with self at the given depth
-    pub(crate) fn combine_ind(&self, ind: u8, depth: u8) -> Self {
-        let shift = P::MAX_DEPTH - depth + 1;
-        let ind_4: <P::Data as OrderedBinary>::Ordered = (ind & 4).into();
-        let ind_2: <P::Data as OrderedBinary>::Ordered = (ind & 2).into();
-        let ind_1: <P::Data as OrderedBinary>::Ordered = (ind & 1).into();
-        if shift >= 32 {
-            PointData([
-                ind_4 >> (shift - 3),
-                ind_2 >> (shift - 2),
-                ind_1 >> (shift - 1),
-            ])
+    /// Returns the smallest and largest points which share the first `depth` bits with self
+    pub(crate) fn cell_bounds(&self, depth: u8) -> (Self, Self) {
+        let low_mask = if depth >= P::MAX_DEPTH {
+            <<P::Data as OrderedBinary>::Ordered as Unsigned>::ZERO
         } else {
-            PointData([
-                self.0[0] >> shift << shift | ind_4 >> 3 << shift,
-                self.0[1] >> shift << shift | ind_2 >> 2 << shift,
-                self.0[2] >> shift << shift | ind_1 >> 1 << shift,
-            ])
-        }
+            <P::Data as OrderedBinary>::Ordered::MAX >> depth
+        };
+        let high_mask = <P::Data as OrderedBinary>::Ordered::MAX ^ low_mask;
+        (
+            PointData(self.0.map(|n| n & high_mask)),
+            PointData(self.0.map(|n| n | low_mask)),
+        )
     }
 }
 
@@ -9,6 +9,11 @@ pub trait OrderedBinary:
 {
     /// A generic constant for the number `0`.
     const ZERO: Self;
+    /// The name of this type saved by [`Octree::to_bytes`](crate::Octree::to_bytes), so that a tree
+    /// can't be loaded with a different coordinate type of the same size.
+    ///
+    /// This should be overridden (with a name that won't change) for your own types.
+    const NAME: &'static str = "";
     /// The unsigned integer this type will be converted to and from with the *_ordered methods.
     type Ordered: Unsigned;
     /// Converts this number into its ordered format.
@@ -16,20 +21,60 @@ pub trait OrderedBinary:
     /// Converts from an ordered format back into `Self`.
     fn from_ordered(ordered: Self::Ordered) -> Self;
 
-    /// This should be overridden for unsigned types (because they can't do naive subtraction).
+    /// This should be overridden for integer types (so that it saturates instead of overflowing, and because
+    /// unsigned types can't do naive subtraction).
     fn distance_squared(&self, other: &Self) -> Self {
         let dist = self.clone() - other.clone();
         dist.clone() * dist
     }
 
+    /// Adds two squared distances together, this should be overridden for integer types so that it
+    /// saturates instead of overflowing.
+    fn add_distances(&self, other: &Self) -> Self {
+        self.clone() + other.clone()
+    }
+
+    /// Multiplies a squared distance by a factor, this should be overridden for integer types so
+    /// that it saturates instead of overflowing.
+    #[doc(hidden)]
+    fn scale_distance(&self, factor: &Self) -> Self {
+        self.clone() * factor.clone()
+    }
+
     /// Used to filter out NaNs from floats, this simply means that no filtering should be done based on this number.
     fn is_irrelevant(&self) -> bool {
         false
     }
+
+    /// Returns `false` for NaN and infinities, this should be overridden for floating point types.
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    /// Returns which of the 8 children of a split cell (as bitflags) could be within `sqr_dist`,
+    /// where `axes[a]` holds the squared distances along axis `a` to the low and high halves of the
+    /// cell.
+    ///
+    /// This is the hot path of [`within`](crate::Octree::within) so the float types do it with SIMD
+    /// if the `simd` feature is enabled.
+    #[doc(hidden)]
+    fn children_within(axes: &[[Self; 2]; 3], sqr_dist: &Self) -> u8 {
+        let mut mask = 0;
+        for i in 0..8 {
+            let closest = axes[0][i >> 2 & 1]
+                .add_distances(&axes[1][i >> 1 & 1])
+                .add_distances(&axes[2][i & 1]);
+            if closest <= *sqr_dist || closest.is_irrelevant() {
+                mask |= 1 << i;
+            }
+        }
+        mask
+    }
 }
 
 impl<U: Unsigned> OrderedBinary for U {
     const ZERO: Self = Self::ZERO;
+    const NAME: &'static str = <Self as Unsigned>::NAME;
     type Ordered = Self;
     fn to_ordered(&self) -> Self {
         *self
@@ -43,12 +88,19 @@ impl<U: Unsigned> OrderedBinary for U {
         } else {
             *other - *self
         };
-        dist * dist
+        dist.saturating_mul(dist)
+    }
+    fn add_distances(&self, other: &Self) -> Self {
+        Unsigned::saturating_add(*self, *other)
+    }
+    fn scale_distance(&self, factor: &Self) -> Self {
+        Unsigned::saturating_mul(*self, *factor)
     }
 }
 
 impl OrderedBinary for i16 {
     const ZERO: i16 = 0;
+    const NAME: &'static str = "i16";
     type Ordered = u16;
     fn to_ordered(&self) -> Self::Ordered {
         u16::from_ne_bytes(self.to_ne_bytes()) ^ (1_u16 << 15)
@@ -56,10 +108,21 @@ impl OrderedBinary for i16 {
     fn from_ordered(ordered: u16) -> Self {
         i16::from_ne_bytes((ordered ^ (1_u16 << 15)).to_ne_bytes())
     }
+    fn distance_squared(&self, other: &Self) -> Self {
+        let dist = (i32::from(*self) - i32::from(*other)).unsigned_abs();
+        i16::try_from(dist.saturating_mul(dist)).unwrap_or(i16::MAX)
+    }
+    fn add_distances(&self, other: &Self) -> Self {
+        self.saturating_add(*other)
+    }
+    fn scale_distance(&self, factor: &Self) -> Self {
+        self.saturating_mul(*factor)
+    }
 }
 
 impl OrderedBinary for i32 {
     const ZERO: i32 = 0;
+    const NAME: &'static str = "i32";
     type Ordered = u32;
     fn to_ordered(&self) -> Self::Ordered {
         u32::from_ne_bytes(self.to_ne_bytes()) ^ (1_u32 << 31)
@@ -67,10 +130,21 @@ impl OrderedBinary for i32 {
     fn from_ordered(ordered: u32) -> Self {
         i32::from_ne_bytes((ordered ^ (1_u32 << 31)).to_ne_bytes())
     }
+    fn distance_squared(&self, other: &Self) -> Self {
+        let dist = (i64::from(*self) - i64::from(*other)).unsigned_abs();
+        i32::try_from(dist.saturating_mul(dist)).unwrap_or(i32::MAX)
+    }
+    fn add_distances(&self, other: &Self) -> Self {
+        self.saturating_add(*other)
+    }
+    fn scale_distance(&self, factor: &Self) -> Self {
+        self.saturating_mul(*factor)
+    }
 }
 
 impl OrderedBinary for i64 {
     const ZERO: i64 = 0;
+    const NAME: &'static str = "i64";
     type Ordered = u64;
     fn to_ordered(&self) -> Self::Ordered {
         u64::from_ne_bytes(self.to_ne_bytes()) ^ (1_u64 << 63)
@@ -78,32 +152,95 @@ impl OrderedBinary for i64 {
     fn from_ordered(ordered: u64) -> Self {
         i64::from_ne_bytes((ordered ^ (1_u64 << 63)).to_ne_bytes())
     }
+    fn distance_squared(&self, other: &Self) -> Self {
+        let dist = (i128::from(*self) - i128::from(*other)).unsigned_abs();
+        i64::try_from(dist.saturating_mul(dist)).unwrap_or(i64::MAX)
+    }
+    fn add_distances(&self, other: &Self) -> Self {
+        self.saturating_add(*other)
+    }
+    fn scale_distance(&self, factor: &Self) -> Self {
+        self.saturating_mul(*factor)
+    }
 }
 
 impl OrderedBinary for f32 {
     const ZERO: f32 = 0.0;
+    const NAME: &'static str = "f32";
     type Ordered = u32;
     fn to_ordered(&self) -> Self::Ordered {
-        u32::from_ne_bytes(self.to_ne_bytes()) ^ (1_u32 << 31)
+        // Negative numbers are flipped entirely so that larger magnitudes come first
+        let bits = self.to_bits();
+        if bits >> 31 == 1 {
+            !bits
+        } else {
+            bits | (1_u32 << 31)
+        }
     }
     fn from_ordered(ordered: u32) -> Self {
-        f32::from_ne_bytes((ordered ^ (1_u32 << 31)).to_ne_bytes())
+        f32::from_bits(if ordered >> 31 == 1 {
+            ordered ^ (1_u32 << 31)
+        } else {
+            !ordered
+        })
     }
     fn is_irrelevant(&self) -> bool {
         self.is_nan()
     }
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+    #[cfg(feature = "simd")]
+    #[allow(clippy::cast_sign_loss)] // The mask only has 8 bits
+    fn children_within(axes: &[[f32; 2]; 3], sqr_dist: &f32) -> u8 {
+        use wide::{f32x8, CmpLe};
+        let [[x0, x1], [y0, y1], [z0, z1]] = *axes;
+        let closest = f32x8::new([x0, x0, x0, x0, x1, x1, x1, x1])
+            + f32x8::new([y0, y0, y1, y1, y0, y0, y1, y1])
+            + f32x8::new([z0, z1, z0, z1, z0, z1, z0, z1]);
+        let within = closest.cmp_le(f32x8::splat(*sqr_dist)) | closest.is_nan();
+        within.move_mask() as u8
+    }
 }
 
 impl OrderedBinary for f64 {
     const ZERO: f64 = 0.0;
+    const NAME: &'static str = "f64";
     type Ordered = u64;
     fn to_ordered(&self) -> Self::Ordered {
-        u64::from_ne_bytes(self.to_ne_bytes()) ^ (1_u64 << 63)
+        // Negative numbers are flipped entirely so that larger magnitudes come first
+        let bits = self.to_bits();
+        if bits >> 63 == 1 {
+            !bits
+        } else {
+            bits | (1_u64 << 63)
+        }
     }
     fn from_ordered(ordered: u64) -> Self {
-        f64::from_ne_bytes((ordered ^ (1_u64 << 63)).to_ne_bytes())
+        f64::from_bits(if ordered >> 63 == 1 {
+            ordered ^ (1_u64 << 63)
+        } else {
+            !ordered
+        })
     }
     fn is_irrelevant(&self) -> bool {
         self.is_nan()
     }
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+    #[cfg(feature = "simd")]
+    #[allow(clippy::cast_sign_loss)] // The mask only has 8 bits
+    fn children_within(axes: &[[f64; 2]; 3], sqr_dist: &f64) -> u8 {
+        use wide::{f64x4, CmpLe};
+        let [[x0, x1], [y0, y1], [z0, z1]] = *axes;
+        let sqr_dist = f64x4::splat(*sqr_dist);
+        let (y, z) = (f64x4::new([y0, y0, y1, y1]), f64x4::new([z0, z1, z0, z1]));
+        // Added in the same order as the scalar version so that they round the same way
+        let low = f64x4::splat(x0) + y + z;
+        let high = f64x4::splat(x1) + y + z;
+        let low = low.cmp_le(sqr_dist) | low.is_nan();
+        let high = high.cmp_le(sqr_dist) | high.is_nan();
+        (low.move_mask() | high.move_mask() << 4) as u8
+    }
 }
@@ -2,6 +2,8 @@ use std::fmt::{Binary, Debug};
 use std::hash::Hash;
 use std::ops::{Add, BitAnd, BitOr, BitXor, Mul, Shl, Shr, Sub};
 
+use crate::binary::BinaryPayload;
+
 mod sealed {
     pub trait Sealed {}
     impl Sealed for u8 {}
@@ -11,8 +13,22 @@ mod sealed {
     impl Sealed for u128 {}
 }
 
+/// Lets the ordered types be serialized if the `serde` feature is enabled.
+#[cfg(feature = "serde")]
+pub trait MaybeSerde: serde::Serialize + serde::de::DeserializeOwned {}
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + serde::de::DeserializeOwned> MaybeSerde for T {}
+
+/// Lets the ordered types be serialized if the `serde` feature is enabled.
+#[cfg(not(feature = "serde"))]
+pub trait MaybeSerde {}
+#[cfg(not(feature = "serde"))]
+impl<T> MaybeSerde for T {}
+
 pub trait Unsigned:
     sealed::Sealed
+    + BinaryPayload
+    + MaybeSerde
     + Binary
     + BitAnd<Self, Output = Self>
     + BitOr<Output = Self>
@@ -34,41 +50,79 @@ pub trait Unsigned:
 {
     const ZERO: Self;
     const MAX: Self;
+    const NAME: &'static str;
     fn leading_zeros(self) -> u8;
+    fn saturating_add(self, other: Self) -> Self;
+    fn saturating_mul(self, other: Self) -> Self;
 }
 
 impl Unsigned for u8 {
     const ZERO: u8 = 0;
     const MAX: u8 = u8::MAX;
+    const NAME: &'static str = "u8";
     fn leading_zeros(self) -> u8 {
         self.leading_zeros() as u8
     }
+    fn saturating_add(self, other: u8) -> u8 {
+        u8::saturating_add(self, other)
+    }
+    fn saturating_mul(self, other: u8) -> u8 {
+        u8::saturating_mul(self, other)
+    }
 }
 impl Unsigned for u16 {
     const ZERO: u16 = 0;
     const MAX: u16 = u16::MAX;
+    const NAME: &'static str = "u16";
     fn leading_zeros(self) -> u8 {
         self.leading_zeros() as u8
     }
+    fn saturating_add(self, other: u16) -> u16 {
+        u16::saturating_add(self, other)
+    }
+    fn saturating_mul(self, other: u16) -> u16 {
+        u16::saturating_mul(self, other)
+    }
 }
 impl Unsigned for u32 {
     const ZERO: u32 = 0;
     const MAX: u32 = u32::MAX;
+    const NAME: &'static str = "u32";
     fn leading_zeros(self) -> u8 {
         self.leading_zeros() as u8
     }
+    fn saturating_add(self, other: u32) -> u32 {
+        u32::saturating_add(self, other)
+    }
+    fn saturating_mul(self, other: u32) -> u32 {
+        u32::saturating_mul(self, other)
+    }
 }
 impl Unsigned for u64 {
     const ZERO: u64 = 0;
     const MAX: u64 = u64::MAX;
+    const NAME: &'static str = "u64";
     fn leading_zeros(self) -> u8 {
         self.leading_zeros() as u8
     }
+    fn saturating_add(self, other: u64) -> u64 {
+        u64::saturating_add(self, other)
+    }
+    fn saturating_mul(self, other: u64) -> u64 {
+        u64::saturating_mul(self, other)
+    }
 }
 impl Unsigned for u128 {
     const ZERO: u128 = 0;
     const MAX: u128 = u128::MAX;
+    const NAME: &'static str = "u128";
     fn leading_zeros(self) -> u8 {
         self.leading_zeros() as u8
     }
+    fn saturating_add(self, other: u128) -> u128 {
+        u128::saturating_add(self, other)
+    }
+    fn saturating_mul(self, other: u128) -> u128 {
+        u128::saturating_mul(self, other)
+    }
 }
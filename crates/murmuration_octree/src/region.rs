@@ -0,0 +1,208 @@
+use super::point::{ordered::OrderedBinary, Point, PointData};
+
+/// How a cell of the tree overlaps with a [`Region`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Overlap {
+    Outside,
+    Partial,
+    Inside,
+}
+
+/// A shape which the tree can be queried over.
+pub(crate) trait Region<P: Point> {
+    /// Returns how the cell between `min` and `max` (inclusive) overlaps with this region.
+    fn overlap(&self, min: &PointData<P>, max: &PointData<P>) -> Overlap;
+    /// Returns `true` if the point is inside this region.
+    fn contains(&self, point: &PointData<P>) -> bool;
+
+    /// Returns which of the 8 child cells at `depth` below `point`'s cell (as bitflags) aren't
+    /// entirely outside this region.
+    fn overlapping_children(&self, point: &PointData<P>, depth: u8) -> u8 {
+        let mut mask = 0;
+        for i in 0..8 {
+            let (min, max) = point.combine_ind(i, depth).cell_bounds(depth);
+            if self.overlap(&min, &max) != Overlap::Outside {
+                mask |= 1 << i;
+            }
+        }
+        mask
+    }
+}
+
+/// All points within `sqrt(sqr_dist)` of `centre`.
+pub(crate) struct Sphere<P: Point> {
+    pub(crate) centre: PointData<P>,
+    pub(crate) sqr_dist: P::Data,
+}
+
+/// Returns the squared distances from `centre` to the closest and furthest points of the cell between
+/// `min` and `max` (inclusive).
+pub(crate) fn distance_bounds<P: Point>(
+    centre: &PointData<P>,
+    min: &PointData<P>,
+    max: &PointData<P>,
+) -> (P::Data, P::Data) {
+    let mut closest = P::Data::ZERO;
+    let mut furthest = P::Data::ZERO;
+    for i in 0..=2 {
+        let (axis_closest, axis_furthest) = axis_distance_bounds(centre, min, max, i);
+        closest = closest.add_distances(&axis_closest);
+        furthest = furthest.add_distances(&axis_furthest);
+    }
+    (closest, furthest)
+}
+
+/// Like [`distance_bounds`], but only along axis `i`
+fn axis_distance_bounds<P: Point>(
+    centre: &PointData<P>,
+    min: &PointData<P>,
+    max: &PointData<P>,
+    i: usize,
+) -> (P::Data, P::Data) {
+    let centre_i = P::Data::from_ordered(centre.0[i]);
+    let to_min = centre_i.distance_squared(&P::Data::from_ordered(min.0[i]));
+    let to_max = centre_i.distance_squared(&P::Data::from_ordered(max.0[i]));
+    if centre.0[i] < min.0[i] {
+        (to_min, to_max)
+    } else if centre.0[i] > max.0[i] {
+        (to_max, to_min)
+    } else if to_min > to_max || to_min.is_irrelevant() {
+        (P::Data::ZERO, to_min)
+    } else {
+        (P::Data::ZERO, to_max)
+    }
+}
+
+impl<P: Point> Region<P> for Sphere<P> {
+    fn overlap(&self, min: &PointData<P>, max: &PointData<P>) -> Overlap {
+        let (closest, furthest) = distance_bounds(&self.centre, min, max);
+        if closest <= self.sqr_dist || closest.is_irrelevant() {
+            if furthest <= self.sqr_dist {
+                Overlap::Inside
+            } else {
+                Overlap::Partial
+            }
+        } else {
+            Overlap::Outside
+        }
+    }
+
+    fn contains(&self, point: &PointData<P>) -> bool {
+        point.distance_squared(&self.centre) <= self.sqr_dist
+    }
+
+    /// Every child is either the low or high half of its parent on each axis, so this only needs
+    /// the distance to each half along each axis which are then added up for all 8 at once
+    fn overlapping_children(&self, point: &PointData<P>, depth: u8) -> u8 {
+        let (min, max) = point.cell_bounds(depth - 1);
+        let half = <P::Data as OrderedBinary>::Ordered::from(1) << (P::MAX_DEPTH - depth);
+        let axes = std::array::from_fn(|i| {
+            let mid = min.0[i] | half;
+            [
+                self.axis_distance(i, min.0[i], mid - 1.into()),
+                self.axis_distance(i, mid, max.0[i]),
+            ]
+        });
+        P::Data::children_within(&axes, &self.sqr_dist)
+    }
+}
+
+impl<P: Point> Sphere<P> {
+    /// Returns the squared distance along `axis` from the centre to the closest point between `min`
+    /// and `max`
+    fn axis_distance(
+        &self,
+        axis: usize,
+        min: <P::Data as OrderedBinary>::Ordered,
+        max: <P::Data as OrderedBinary>::Ordered,
+    ) -> P::Data {
+        let centre = self.centre.0[axis];
+        if centre < min {
+            P::Data::from_ordered(centre).distance_squared(&P::Data::from_ordered(min))
+        } else if centre > max {
+            P::Data::from_ordered(centre).distance_squared(&P::Data::from_ordered(max))
+        } else {
+            P::Data::ZERO
+        }
+    }
+}
+
+/// All points in the ellipsoid around `centre` with the given radius along each axis.
+///
+/// A point is inside if the sum of `(distance along axis / radius)^2` is at most `1`, which is
+/// multiplied out by the squares of all the radii (`weights[i] * distance[i]^2 <= limit`) so that
+/// it works for integer coordinates.
+pub(crate) struct Ellipsoid<P: Point> {
+    pub(crate) centre: PointData<P>,
+    weights: [P::Data; 3], // The product of the other two squared radii for each axis
+    limit: P::Data,        // The product of all three squared radii
+}
+
+impl<P: Point> Ellipsoid<P> {
+    pub(crate) fn new(centre: PointData<P>, radii: &[P::Data; 3]) -> Self {
+        let sqr_radii = radii
+            .clone()
+            .map(|radius| radius.distance_squared(&P::Data::ZERO));
+        let weights =
+            std::array::from_fn(|i| sqr_radii[(i + 1) % 3].scale_distance(&sqr_radii[(i + 2) % 3]));
+        let limit = weights[0].scale_distance(&sqr_radii[0]);
+        Self {
+            centre,
+            weights,
+            limit,
+        }
+    }
+}
+
+impl<P: Point> Region<P> for Ellipsoid<P> {
+    fn overlap(&self, min: &PointData<P>, max: &PointData<P>) -> Overlap {
+        let mut closest = P::Data::ZERO;
+        let mut furthest = P::Data::ZERO;
+        for i in 0..=2 {
+            let (axis_closest, axis_furthest) = axis_distance_bounds(&self.centre, min, max, i);
+            closest = closest.add_distances(&axis_closest.scale_distance(&self.weights[i]));
+            furthest = furthest.add_distances(&axis_furthest.scale_distance(&self.weights[i]));
+        }
+        if closest <= self.limit || closest.is_irrelevant() {
+            if furthest <= self.limit {
+                Overlap::Inside
+            } else {
+                Overlap::Partial
+            }
+        } else {
+            Overlap::Outside
+        }
+    }
+
+    fn contains(&self, point: &PointData<P>) -> bool {
+        let mut total = P::Data::ZERO;
+        for i in 0..=2 {
+            let distance = P::Data::from_ordered(point.0[i])
+                .distance_squared(&P::Data::from_ordered(self.centre.0[i]));
+            total = total.add_distances(&distance.scale_distance(&self.weights[i]));
+        }
+        total <= self.limit
+    }
+}
+
+/// All points between `min` and `max` (inclusive) on every axis.
+pub(crate) struct Aabb<P: Point> {
+    pub(crate) min: PointData<P>,
+    pub(crate) max: PointData<P>,
+}
+
+impl<P: Point> Region<P> for Aabb<P> {
+    fn overlap(&self, min: &PointData<P>, max: &PointData<P>) -> Overlap {
+        if (0..=2).any(|i| max.0[i] < self.min.0[i] || min.0[i] > self.max.0[i]) {
+            Overlap::Outside
+        } else if (0..=2).all(|i| self.min.0[i] <= min.0[i] && max.0[i] <= self.max.0[i]) {
+            Overlap::Inside
+        } else {
+            Overlap::Partial
+        }
+    }
+
+    fn contains(&self, point: &PointData<P>) -> bool {
+        (0..=2).all(|i| self.min.0[i] <= point.0[i] && point.0[i] <= self.max.0[i])
+    }
+}
@@ -1,17 +1,35 @@
-use std::collections::VecDeque;
 use std::ops::Deref;
 
 use super::{
+    aggregate::Aggregate,
+    path::Path,
     point::{Point, PointData},
     Branch, BranchKey, Octree,
 };
 
-impl<D: PartialEq, P: Point> Octree<D, P> {
+impl<D: PartialEq, P: Point, A: Aggregate<D, P>> Octree<D, P, A> {
     /// Removes the given `data` from `point` in the tree if it exists, otherwise returns `false`.
     pub fn remove(&mut self, point: &P, data: &D) -> bool {
         let point = point.get_point();
-        if let Ok((leaf, parents)) = self.get_leaf_parents(&point) {
-            self.remove_from_parent_chain(leaf, parents, data)
+        if !point.is_finite() && self.remove_parked(&point, data) {
+            return true;
+        }
+        if let Some((leaf, parents)) = self.get_leaf_parents(&point) {
+            self.remove_from_parent_chain(leaf, &parents, &point, data)
+        } else {
+            false
+        }
+    }
+
+    /// Removes the given `data` from the parked items if it is there with `point`.
+    pub(crate) fn remove_parked(&mut self, point: &PointData<P>, data: &D) -> bool {
+        if let Some(ind) = self
+            .parked
+            .iter()
+            .position(|(p, d)| p == point && d == data)
+        {
+            self.parked.swap_remove(ind);
+            true
         } else {
             false
         }
@@ -21,47 +39,72 @@ impl<D: PartialEq, P: Point> Octree<D, P> {
     pub(crate) fn remove_from_parent_chain(
         &mut self,
         leaf: BranchKey,
-        parents: VecDeque<ParentBranch>,
+        parents: &Path<ParentBranch>,
+        point: &PointData<P>,
         data: &D,
     ) -> bool {
         let mut leaf = leaf;
-        let mut parents = parents;
-        let child = loop {
-            let Branch::Leaf {
-                data: leaf_data,
-                child,
-                ..
-            } = self.get_branch(leaf)
-            else {
-                unreachable!()
-            };
+        let mut previous = None; // The duplicate leaf before `leaf` at the same point, if any
+        let refresh_point = Self::AGGREGATES.then(|| point.clone());
 
-            if data == leaf_data {
-                break child;
-            } else if let Some(child) = child {
-                parents.push_front(ParentBranch {
-                    branch: leaf,
-                    ind: None,
-                });
-                leaf = *child;
-            } else {
+        let child = if let Branch::Bucket { items } = self.get_branch_mut(leaf) {
+            let Some(ind) = items.iter().position(|(p, d)| p == point && d == data) else {
                 return false;
+            };
+            items.swap_remove(ind);
+            // The bucket is only removed (like a leaf) once it is empty
+            if !items.is_empty() {
+                self.exclude_item(parents);
+                if let Some(point) = refresh_point {
+                    self.refresh_aggregates(&point);
+                }
+                return true;
+            }
+            None
+        } else {
+            loop {
+                let Branch::Leaf {
+                    data: leaf_data,
+                    child,
+                    ..
+                } = self.get_branch(leaf)
+                else {
+                    unreachable!()
+                };
+
+                if data == leaf_data {
+                    break *child;
+                } else if let Some(child) = child {
+                    previous = Some(leaf);
+                    leaf = *child;
+                } else {
+                    return false;
+                }
             }
         };
 
-        if let Some(parent) = parents.front() {
+        // The item is definitely being removed so it is no longer below any of its parents
+        self.exclude_item(parents);
+
+        if let Some(previous) = previous {
+            let Branch::Leaf {
+                child: previous_child,
+                ..
+            } = self.get_branch_mut(previous)
+            else {
+                unreachable!()
+            };
+            *previous_child = child;
+        } else if let Some(parent) = parents.top() {
             if let Some(new_child) = child {
-                parent.set_child(self, *new_child);
+                parent.set_child(self, new_child);
             } else {
                 let info = match self.get_branch_mut(**parent) {
-                    Branch::Leaf { child, .. } => {
-                        *child = None;
-                        None
-                    }
                     Branch::Split {
                         children,
                         occupied,
                         depth,
+                        ..
                     } => {
                         let ind = parent.ind.unwrap() as usize;
                         *occupied &= 255 ^ (1_u8.wrapping_shl(ind as u32));
@@ -83,7 +126,9 @@ impl<D: PartialEq, P: Point> Octree<D, P> {
                             ))
                         }
                     }
-                    Branch::Skip { .. } => unreachable!(),
+                    Branch::Skip { .. } | Branch::Leaf { .. } | Branch::Bucket { .. } => {
+                        unreachable!()
+                    }
                 };
 
                 // If there is a new_child we want to re-parent it onto the item above
@@ -93,6 +138,7 @@ impl<D: PartialEq, P: Point> Octree<D, P> {
                     let child_point = loop {
                         match self.get_branch(sub_child) {
                             Branch::Leaf { point, .. } | Branch::Skip { point, .. } => break point,
+                            Branch::Bucket { items } => break &items[0].0,
                             Branch::Split { children, .. } => {
                                 if let Some(child) = children.iter().flatten().next() {
                                     sub_child = *child;
@@ -102,10 +148,13 @@ impl<D: PartialEq, P: Point> Octree<D, P> {
                         }
                     };
                     let skip_branch = if have_split {
+                        let child_point = child_point.clone();
+                        let len = self.branch_len(child);
                         self.add_branch(Branch::Skip {
-                            point: child_point.clone(),
+                            point: child_point,
                             point_depth: depth,
                             child,
+                            len,
                         })
                     } else {
                         child
@@ -128,23 +177,35 @@ impl<D: PartialEq, P: Point> Octree<D, P> {
                 }
             }
         } else {
-            self.root = *child;
+            self.root = child;
         }
         self.remove_branch(leaf);
+        if let Some(point) = refresh_point {
+            self.refresh_aggregates(&point);
+        }
         true
     }
 }
 
-impl<D, P: Point> Octree<D, P> {
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Removes an item from the length of every split or skip branch in `parents`
+    fn exclude_item(&mut self, parents: &Path<ParentBranch>) {
+        for parent in parents.iter() {
+            if let Branch::Split { len, .. } | Branch::Skip { len, .. } =
+                self.get_branch_mut(**parent)
+            {
+                *len -= 1;
+            }
+        }
+    }
+
     /// Returns the leaf and chain of branches leading to it
     pub(crate) fn get_leaf_parents(
         &self,
         point: &PointData<P>,
-    ) -> Result<(BranchKey, VecDeque<ParentBranch>), VecDeque<ParentBranch>> {
-        let Some(mut branch) = self.root else {
-            return Err(VecDeque::new());
-        };
-        let mut parents = VecDeque::new();
+    ) -> Option<(BranchKey, Path<ParentBranch>)> {
+        let mut branch = self.root?;
+        let mut parents = Path::new();
         let mut depth = 0;
 
         loop {
@@ -152,35 +213,40 @@ impl<D, P: Point> Octree<D, P> {
                 Branch::Leaf {
                     point: skip_point, ..
                 } => {
-                    return (point == skip_point)
-                        .then_some((branch, parents.clone()))
-                        .ok_or(parents);
+                    return (point == skip_point).then_some((branch, parents));
+                }
+                Branch::Bucket { items } => {
+                    return items
+                        .iter()
+                        .any(|(p, _)| p == point)
+                        .then_some((branch, parents));
                 }
                 Branch::Skip {
                     point: skip_point,
                     point_depth: skip_depth,
                     child,
+                    ..
                 } => {
                     let shared = (point ^ skip_point).leading_zeros();
                     if shared >= *skip_depth {
-                        parents.push_front(ParentBranch { branch, ind: None });
+                        parents.push(ParentBranch { branch, ind: None });
                         branch = *child;
                         depth = *skip_depth;
                     } else {
-                        return Err(parents);
+                        return None;
                     }
                 }
                 Branch::Split { children, .. } => {
                     let ind = point.nth(depth);
                     if let Some(child) = children[ind as usize] {
-                        parents.push_front(ParentBranch {
+                        parents.push(ParentBranch {
                             branch,
                             ind: Some(ind),
                         });
                         branch = child;
                         depth += 1;
                     } else {
-                        return Err(parents);
+                        return None;
                     }
                 }
             }
@@ -188,7 +254,7 @@ impl<D, P: Point> Octree<D, P> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub(crate) struct ParentBranch {
     pub(crate) branch: BranchKey,
     pub(crate) ind: Option<u8>,
@@ -202,13 +268,14 @@ impl Deref for ParentBranch {
 }
 
 impl ParentBranch {
-    fn set_child<D, P: Point>(&self, octree: &mut Octree<D, P>, new_child: BranchKey) {
+    fn set_child<D, P: Point, A>(self, octree: &mut Octree<D, P, A>, new_child: BranchKey) {
         match octree.get_branch_mut(self.branch) {
             Branch::Leaf { child, .. } => *child = Some(new_child),
             Branch::Skip { child, .. } => *child = new_child,
             Branch::Split { children, .. } => {
                 children[self.ind.unwrap() as usize] = Some(new_child);
             }
+            Branch::Bucket { .. } => unreachable!(),
         }
     }
 }
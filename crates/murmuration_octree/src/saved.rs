@@ -0,0 +1,431 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use super::{
+    binary::{BinaryPayload, Node, Reader, BUCKET, LEAF, MAGIC, NODE_SIZE, SKIP, SPLIT, VERSION},
+    error::LoadError,
+    point::{ordered::OrderedBinary, Point, PointData},
+    region::{Aabb, Overlap, Region, Sphere},
+    NonFinitePolicy,
+};
+
+/// A tree saved with [`Octree::to_bytes`](crate::Octree::to_bytes) which is queried directly from
+/// the saved bytes, without building anything.
+///
+/// The bytes are checked once when this is created (which reads every node and point but doesn't
+/// allocate), after that queries only read the parts of the bytes they need, so this works well
+/// with a memory-mapped file. Items are decoded as they are returned, so they are returned by value
+/// rather than by reference.
+///
+/// # Example
+/// ```
+/// use murmuration_octree::{Octree, SavedOctree};
+///
+/// let mut tree = Octree::new();
+/// tree.add(&[1.0_f32, 2.0, 3.0], 7_u32);
+/// tree.add(&[50.0, 2.0, 3.0], 8);
+/// let bytes = tree.to_bytes();
+///
+/// let saved = SavedOctree::<u32, [f32; 3]>::from_bytes(&bytes).unwrap();
+/// assert_eq!(saved.within(&[0.0, 0.0, 0.0], 10.0).collect::<Vec<_>>(), [7]);
+/// assert_eq!(saved.get_single(&[50.0, 2.0, 3.0]), Some(8));
+/// ```
+pub struct SavedOctree<'a, D, P: Point> {
+    nodes: &'a [u8], // The root is first and each split's children are stored together
+    points: &'a [u8],
+    data: &'a [u8], // Items are in depth first order so every node covers a contiguous range of them
+    item_count: usize, // Not including parked items, which come after the rest
+    parked_count: usize,
+    non_finite: NonFinitePolicy,
+    bucket_size: usize,
+    marker: PhantomData<(D, P)>,
+}
+
+impl<'a, D: BinaryPayload, P: Point> SavedOctree<'a, D, P> {
+    /// Checks that `bytes` are a tree saved with the same point and data types, which can then be
+    /// queried in place.
+    ///
+    /// # Errors
+    /// Returns a [`LoadError`] if the bytes aren't a tree saved with the same point and data types,
+    /// or if its structure is malformed, so corrupt data can't give wrong results (or panic).
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, LoadError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(LoadError::NotAnOctree);
+        }
+        let version = reader.read::<u16>()?;
+        if version != VERSION {
+            return Err(LoadError::UnsupportedVersion { version });
+        }
+        let max_depth = reader.read::<u8>()?;
+        let non_finite = match reader.read::<u8>()? {
+            0 => NonFinitePolicy::Insert,
+            1 => NonFinitePolicy::Park,
+            _ => return Err(LoadError::InvalidHeader),
+        };
+        let point_size = reader.read::<u32>()? as usize;
+        let data_size = reader.read::<u32>()? as usize;
+        let bucket_size = reader.read_usize()?;
+        let node_count = reader.read_usize()?;
+        let item_count = reader.read_usize()?;
+        let parked_count = reader.read_usize()?;
+        let name_len = reader.read::<u32>()? as usize;
+        let name = reader.take(name_len)?;
+        let data_name_len = reader.read::<u32>()? as usize;
+        let data_name = reader.take(data_name_len)?;
+
+        if max_depth != P::MAX_DEPTH
+            || point_size != <<P::Data as OrderedBinary>::Ordered as BinaryPayload>::SIZE
+            || name != P::Data::NAME.as_bytes()
+        {
+            return Err(LoadError::WrongPointType);
+        }
+        if data_size != D::SIZE || data_name != D::name().as_bytes() {
+            return Err(LoadError::WrongDataType);
+        }
+        // Nodes refer to each other and their items with `u32`s
+        if node_count > u32::MAX as usize || item_count > u32::MAX as usize {
+            return Err(LoadError::InvalidHeader);
+        }
+
+        let total = item_count
+            .checked_add(parked_count)
+            .ok_or(LoadError::Truncated)?;
+        let saved = SavedOctree {
+            nodes: reader.take_array(node_count, NODE_SIZE)?,
+            points: reader.take_array(total, point_size * 3)?,
+            data: reader.take_array(total, data_size)?,
+            item_count,
+            parked_count,
+            non_finite,
+            bucket_size,
+            marker: PhantomData,
+        };
+
+        let mut next_item = 0;
+        let mut reached = 0;
+        if node_count > 0 {
+            saved.check_node(0, 0, &PointData::ZERO, &mut next_item, &mut reached)?;
+        }
+        if next_item != item_count {
+            return Err(LoadError::BadNode { node: 0 });
+        }
+        if reached != node_count {
+            return Err(LoadError::BadNode { node: reached });
+        }
+        Ok(saved)
+    }
+
+    /// Checks the node at `index` given that `point` is in its cell which shares the first `depth`
+    /// bits.
+    ///
+    /// Every node's items must start at `next_item`, so (as no node is empty) a node can't be
+    /// reached twice, and the depth goes up at every split and skip so bad data can't overflow the
+    /// stack.
+    fn check_node(
+        &self,
+        index: usize,
+        depth: u8,
+        point: &PointData<P>,
+        next_item: &mut usize,
+        reached: &mut usize,
+    ) -> Result<(), LoadError> {
+        let bad = LoadError::BadNode { node: index };
+        if index >= self.node_count() {
+            return Err(bad);
+        }
+        *reached += 1;
+        let node = self.node(index);
+        let start = node.items as usize;
+        let Some(end) = start.checked_add(node.len as usize) else {
+            return Err(bad);
+        };
+        if start != *next_item || end <= start || end > self.item_count {
+            return Err(bad);
+        }
+        let shares = |item: usize| (point ^ &self.point(item)).leading_zeros() >= depth;
+
+        match node.kind {
+            SPLIT => {
+                if node.depth != depth + 1
+                    || node.depth > P::MAX_DEPTH
+                    || node.occupied.count_ones() < 2
+                {
+                    return Err(bad);
+                }
+                let occupied = (0..8).filter(|i| node.occupied & (1 << i) != 0);
+                for (child, i) in (node.children as usize..).zip(occupied) {
+                    let child_point = point.combine_ind(i, node.depth);
+                    self.check_node(child, node.depth, &child_point, next_item, reached)?;
+                }
+            }
+            SKIP => {
+                let child = node.children as usize;
+                if node.depth <= depth
+                    || node.depth >= P::MAX_DEPTH
+                    || !shares(start)
+                    || child >= self.node_count()
+                    || self.node(child).kind != SPLIT
+                {
+                    return Err(bad);
+                }
+                let skip_point = self.point(start);
+                self.check_node(child, node.depth, &skip_point, next_item, reached)?;
+            }
+            LEAF => {
+                let leaf_point = self.point(start);
+                if !shares(start) || (start..end).any(|item| self.point(item) != leaf_point) {
+                    return Err(bad);
+                }
+                *next_item = end;
+            }
+            BUCKET => {
+                if !(start..end).all(shares) {
+                    return Err(bad);
+                }
+                *next_item = end;
+            }
+            _ => return Err(bad),
+        }
+        // Every item below a split or skip has been checked, so this checks its range was right
+        if *next_item != end {
+            return Err(bad);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn node_count(&self) -> usize {
+        self.nodes.len() / NODE_SIZE
+    }
+
+    pub(crate) fn node(&self, index: usize) -> Node {
+        Node::read(self.nodes, index)
+    }
+
+    pub(crate) fn point(&self, item: usize) -> PointData<P> {
+        let size = <<P::Data as OrderedBinary>::Ordered as BinaryPayload>::SIZE * 3;
+        PointData(BinaryPayload::read_bytes(
+            &self.points[item * size..(item + 1) * size],
+        ))
+    }
+
+    pub(crate) fn data(&self, item: usize) -> D {
+        D::read_bytes(&self.data[item * D::SIZE..(item + 1) * D::SIZE])
+    }
+
+    pub(crate) fn parked_items(&self) -> impl Iterator<Item = (PointData<P>, D)> + '_ {
+        (self.item_count..self.item_count + self.parked_count)
+            .map(|item| (self.point(item), self.data(item)))
+    }
+
+    /// Returns the number of items in the tree (including any [parked](Self::parked) ones).
+    pub fn len(&self) -> usize {
+        self.item_count + self.parked_count
+    }
+
+    /// Returns `true` if there are no items in the tree.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how the saved tree handled points with a NaN or infinite coordinate.
+    pub fn non_finite_policy(&self) -> NonFinitePolicy {
+        self.non_finite
+    }
+
+    /// Returns the bucket size of the saved tree (see
+    /// [`Octree::set_bucket_size`](crate::Octree::set_bucket_size)).
+    pub fn bucket_size(&self) -> usize {
+        self.bucket_size
+    }
+
+    /// Returns all the items which were parked because their point wasn't finite.
+    pub fn parked(&self) -> impl Iterator<Item = D> + '_ {
+        self.parked_items().map(|(_, data)| data)
+    }
+
+    /// Returns all the items (not including parked ones) and their points, in Morton order.
+    pub fn iter(&self) -> impl Iterator<Item = ([P::Data; 3], D)> + '_ {
+        (0..self.item_count).map(|item| (self.point(item).to_array(), self.data(item)))
+    }
+
+    /// Returns all items at the given `point`.
+    pub fn get(&self, point: &P) -> impl Iterator<Item = D> + '_ {
+        let point = point.get_point();
+        self.get_items(&point)
+            .filter(move |item| self.point(*item) == point)
+            .map(|item| self.data(item))
+    }
+
+    /// Returns one of the items at the given `point` or `None` if there aren't any.
+    pub fn get_single(&self, point: &P) -> Option<D> {
+        self.get(point).next()
+    }
+
+    /// Returns the range of items in the leaf or bucket which `point` would be in
+    fn get_items(&self, point: &PointData<P>) -> Range<usize> {
+        let mut index = 0;
+        let mut depth = 0;
+        while index < self.node_count() {
+            let node = self.node(index);
+            let cell_depth = cell_depth::<P>(&node, depth);
+            if (point ^ &self.point(node.items as usize)).leading_zeros() < cell_depth {
+                break;
+            }
+            match node.kind {
+                SPLIT => {
+                    let ind = point.nth(depth);
+                    if node.occupied & (1 << ind) == 0 {
+                        break;
+                    }
+                    let before = node.occupied & ((1 << ind) - 1);
+                    index = node.children as usize + before.count_ones() as usize;
+                }
+                SKIP => index = node.children as usize,
+                _ => return node.item_range(),
+            }
+            depth = node.depth;
+        }
+        0..0
+    }
+
+    /// Returns all items within `distance` of `point`, in an unspecified order.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn within(&self, point: &P, distance: P::Data) -> impl Iterator<Item = D> + '_ {
+        self.iter_in(Sphere {
+            centre: point.get_point(),
+            sqr_dist: distance.distance_squared(&P::Data::ZERO),
+        })
+    }
+
+    /// Returns all items between `min` and `max` (inclusive) on every axis, in an unspecified order.
+    pub fn in_aabb(&self, min: &P, max: &P) -> impl Iterator<Item = D> + '_ {
+        self.iter_in(Aabb {
+            min: min.get_point(),
+            max: max.get_point(),
+        })
+    }
+
+    fn iter_in<R: Region<P>>(&self, region: R) -> SavedIter<'_, 'a, D, P, R> {
+        SavedIter {
+            saved: self,
+            region,
+            stack: if self.node_count() == 0 {
+                vec![]
+            } else {
+                vec![(0, 0)]
+            },
+            items: 0..0,
+            check: false,
+        }
+    }
+
+    /// Returns the number of items within `distance` of `point`.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn count_within(&self, point: &P, distance: P::Data) -> usize {
+        self.count_in(&Sphere {
+            centre: point.get_point(),
+            sqr_dist: distance.distance_squared(&P::Data::ZERO),
+        })
+    }
+
+    /// Returns the number of items between `min` and `max` (inclusive) on every axis.
+    pub fn count_in_aabb(&self, min: &P, max: &P) -> usize {
+        self.count_in(&Aabb {
+            min: min.get_point(),
+            max: max.get_point(),
+        })
+    }
+
+    fn count_in(&self, region: &impl Region<P>) -> usize {
+        let mut count = 0;
+        let mut stack = if self.node_count() == 0 {
+            vec![]
+        } else {
+            vec![(0, 0)]
+        };
+        while let Some((index, depth)) = stack.pop() {
+            let node = self.node(index);
+            match self.overlap(&node, depth, region) {
+                Overlap::Outside => {}
+                Overlap::Inside => count += node.len as usize,
+                Overlap::Partial if matches!(node.kind, LEAF | BUCKET) => {
+                    count += node
+                        .item_range()
+                        .filter(|item| region.contains(&self.point(*item)))
+                        .count();
+                }
+                Overlap::Partial => push_children(&node, &mut stack),
+            }
+        }
+        count
+    }
+
+    /// Returns how the cell of `node` (which is below `depth` bits of splits) overlaps `region`
+    fn overlap(&self, node: &Node, depth: u8, region: &impl Region<P>) -> Overlap {
+        let (min, max) = self
+            .point(node.items as usize)
+            .cell_bounds(cell_depth::<P>(node, depth));
+        region.overlap(&min, &max)
+    }
+}
+
+/// Adds the children of a split or skip (with their depths) to `stack`
+fn push_children(node: &Node, stack: &mut Vec<(usize, u8)>) {
+    let children = node.children as usize;
+    if node.kind == SPLIT {
+        let count = node.occupied.count_ones() as usize;
+        stack.extend((children..children + count).map(|child| (child, node.depth)));
+    } else {
+        stack.push((children, node.depth));
+    }
+}
+
+/// Returns the number of leading bits shared by everything in the cell of `node`, which is below
+/// `depth` bits of splits
+fn cell_depth<P: Point>(node: &Node, depth: u8) -> u8 {
+    match node.kind {
+        SKIP => node.depth,
+        LEAF => P::MAX_DEPTH,
+        _ => depth,
+    }
+}
+
+struct SavedIter<'s, 'a, D, P: Point, R> {
+    saved: &'s SavedOctree<'a, D, P>,
+    region: R,
+    stack: Vec<(usize, u8)>, // Nodes to visit and their depths
+    items: Range<usize>,     // The items we are currently going through
+    check: bool,             // Whether the current items need to be checked against the region
+}
+
+impl<D: BinaryPayload, P: Point, R: Region<P>> Iterator for SavedIter<'_, '_, D, P, R> {
+    type Item = D;
+    fn next(&mut self) -> Option<D> {
+        loop {
+            for item in self.items.by_ref() {
+                if !self.check || self.region.contains(&self.saved.point(item)) {
+                    return Some(self.saved.data(item));
+                }
+            }
+
+            let (index, depth) = self.stack.pop()?;
+            let node = self.saved.node(index);
+            match self.saved.overlap(&node, depth, &self.region) {
+                Overlap::Outside => {}
+                Overlap::Inside => {
+                    self.items = node.item_range();
+                    self.check = false;
+                }
+                Overlap::Partial if matches!(node.kind, LEAF | BUCKET) => {
+                    self.items = node.item_range();
+                    self.check = true;
+                }
+                Overlap::Partial => push_children(&node, &mut self.stack),
+            }
+        }
+    }
+}
+
+impl<D: BinaryPayload, P: Point, R: Region<P>> FusedIterator for SavedIter<'_, '_, D, P, R> {}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use nonmax::NonMaxU32;
+use serde::{
+    de::Error as _, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer,
+};
+use slab::Slab;
+
+use super::{
+    aggregate::Aggregate, error::ValidationError, point::Point, Branch, BranchKey, NonFinitePolicy,
+    Octree, PointData,
+};
+
+impl From<BranchKey> for u32 {
+    fn from(key: BranchKey) -> u32 {
+        key.0.into()
+    }
+}
+
+impl TryFrom<u32> for BranchKey {
+    type Error = &'static str;
+    fn try_from(key: u32) -> Result<Self, Self::Error> {
+        NonMaxU32::new(key)
+            .map(BranchKey)
+            .ok_or("branch key can't be u32::MAX")
+    }
+}
+
+/// Serializes the branches along with their keys, as there may be gaps between them.
+struct Branches<'a, D, P: Point, A>(&'a Slab<Branch<D, P, A>>);
+
+impl<D: Serialize, P: Point, A> Serialize for Branches<'_, D, P, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|(key, branch)| (key as u32, branch)))
+    }
+}
+
+/// The structure of the tree (without aggregates, which are recalculated when deserializing).
+impl<D: Serialize, P: Point, A> Serialize for Octree<D, P, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Octree", 5)?;
+        state.serialize_field("branches", &Branches(&self.branches))?;
+        state.serialize_field("root", &self.root)?;
+        state.serialize_field("non_finite", &self.non_finite)?;
+        state.serialize_field("parked", &self.parked)?;
+        state.serialize_field("bucket_size", &self.bucket_size)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(
+    rename = "Octree",
+    bound(deserialize = "D: Deserialize<'de>, A: Default")
+)]
+struct OctreeData<D, P: Point, A> {
+    branches: Vec<(u32, Branch<D, P, A>)>,
+    root: Option<BranchKey>,
+    non_finite: NonFinitePolicy,
+    parked: Vec<(PointData<P>, D)>,
+    bucket_size: usize,
+}
+
+/// The tree is [validated](Octree::validate) after it is deserialized so this will fail (rather than
+/// leaving a tree which panics later) if the data is corrupt.
+impl<'de, D, P, A> Deserialize<'de> for Octree<D, P, A>
+where
+    D: Deserialize<'de>,
+    P: Point,
+    A: Aggregate<D, P>,
+{
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let data = OctreeData::<D, P, A>::deserialize(deserializer)?;
+
+        // The saved keys may have gaps, so the branches are given new ones without any
+        let mut new_keys = HashMap::with_capacity(data.branches.len());
+        for (new, (old, _)) in data.branches.iter().enumerate() {
+            let new = BranchKey::try_from(new as u32).map_err(De::Error::custom)?;
+            if new_keys.insert(*old, new).is_some() {
+                return Err(De::Error::custom(format!("branch {old} is duplicated")));
+            }
+        }
+        let new_key = |key: BranchKey| new_keys.get(&u32::from(key)).copied();
+
+        let mut branches = Slab::with_capacity(data.branches.len());
+        for (key, (_, mut branch)) in data.branches.into_iter().enumerate() {
+            branch.map_keys(new_key).ok_or_else(|| {
+                De::Error::custom(ValidationError::MissingChild { branch: Some(key) })
+            })?;
+            branches.insert(branch);
+        }
+        let root = match data.root {
+            Some(root) => Some(new_key(root).ok_or_else(|| {
+                De::Error::custom(ValidationError::MissingChild { branch: None })
+            })?),
+            None => None,
+        };
+
+        let mut octree = Octree {
+            slab_end: branches.len(),
+            branches,
+            root,
+            non_finite: data.non_finite,
+            parked: data.parked,
+            bucket_size: data.bucket_size,
+            max_items: None,
+        };
+        octree.validate().map_err(De::Error::custom)?;
+        octree.recalculate_aggregates();
+        Ok(octree)
+    }
+}
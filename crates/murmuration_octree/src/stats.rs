@@ -0,0 +1,132 @@
+use std::fmt::{Display, Formatter};
+use std::mem::size_of;
+
+use super::{point::Point, Branch, NodeKind, Octree, PointData, VisitAction};
+
+/// Statistics about the shape and memory use of an [`Octree`], returned by [`Octree::stats`].
+///
+/// These are useful to tell when a tree has degenerated, for example if lots of items are stacked at
+/// the same point then `longest_duplicate_chain` will be very large.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OctreeStats {
+    /// The number of items in the tree (including parked ones).
+    pub items: usize,
+    /// The number of items parked because their point wasn't finite.
+    pub parked: usize,
+    /// The number of split branches (which divide their cell into 8).
+    pub splits: usize,
+    /// The number of skip branches (which skip over layers with no splits).
+    pub skips: usize,
+    /// The number of leaf branches, this is one per item not in a bucket.
+    pub leaves: usize,
+    /// The number of buckets (see [`Octree::set_bucket_size`]).
+    pub buckets: usize,
+    /// The number of distinct points (or buckets) at each level of the tree (how many branches are
+    /// above them).
+    pub depth_histogram: Vec<usize>,
+    /// The largest number of items at exactly the same point (whether chained in leaves or in a
+    /// bucket).
+    pub longest_duplicate_chain: usize,
+    /// The average number of children each split branch has (from 2 to 8).
+    pub average_fan_out: f32,
+    /// The approximate number of bytes of memory used by the tree, not including any heap memory
+    /// owned by the items themselves.
+    pub bytes_used: usize,
+    /// The fraction of branch storage which is empty, see [`Octree::fragmentation`].
+    pub fragmentation: f32,
+}
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Returns statistics about the shape and memory use of the tree, this has to look at every
+    /// branch so shouldn't be called every frame.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn stats(&self) -> OctreeStats {
+        let mut stats = OctreeStats {
+            items: self.len(),
+            parked: self.parked.len(),
+            fragmentation: self.fragmentation(),
+            ..OctreeStats::default()
+        };
+        let mut split_children = 0;
+        self.visit(|node| {
+            match node.kind() {
+                NodeKind::Split => {
+                    stats.splits += 1;
+                    split_children += node.occupied().count_ones() as usize;
+                    return VisitAction::Descend;
+                }
+                NodeKind::Skip => {
+                    stats.skips += 1;
+                    return VisitAction::Descend;
+                }
+                NodeKind::Leaf => {
+                    // Every item in a leaf's chain is at the same point
+                    stats.leaves += node.len();
+                    stats.longest_duplicate_chain = stats.longest_duplicate_chain.max(node.len());
+                }
+                NodeKind::Bucket => {
+                    // Buckets aren't sorted (and items at one point never split out of them, so
+                    //  they can be huge) so sort their points to find the longest run
+                    stats.buckets += 1;
+                    let mut points: Vec<_> = node.point_data().map(|(point, _)| point.0).collect();
+                    points.sort_unstable();
+                    for run in points.chunk_by(|a, b| a == b) {
+                        stats.longest_duplicate_chain =
+                            stats.longest_duplicate_chain.max(run.len());
+                    }
+                }
+            }
+
+            // Only the ends of the tree (leaves and buckets) are counted in the histogram
+            if stats.depth_histogram.len() <= node.level() {
+                stats.depth_histogram.resize(node.level() + 1, 0);
+            }
+            stats.depth_histogram[node.level()] += 1;
+            VisitAction::Descend
+        });
+        if stats.splits > 0 {
+            stats.average_fan_out = split_children as f32 / stats.splits as f32;
+        }
+
+        // A slab entry is an enum of either a branch or the index of the next empty slot
+        stats.bytes_used = size_of::<Self>()
+            + self.branches.capacity() * size_of::<Result<Branch<D, P, A>, usize>>()
+            + self.parked.capacity() * size_of::<(PointData<P>, D)>();
+        for (_, branch) in &self.branches {
+            if let Branch::Bucket { items } = branch {
+                stats.bytes_used += items.capacity() * size_of::<(PointData<P>, D)>();
+            }
+        }
+        stats
+    }
+}
+
+impl Display for OctreeStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "items:                   {}", self.items)?;
+        writeln!(f, "parked:                  {}", self.parked)?;
+        writeln!(f, "splits:                  {}", self.splits)?;
+        writeln!(f, "skips:                   {}", self.skips)?;
+        writeln!(f, "leaves:                  {}", self.leaves)?;
+        writeln!(f, "buckets:                 {}", self.buckets)?;
+        writeln!(
+            f,
+            "longest duplicate chain: {}",
+            self.longest_duplicate_chain
+        )?;
+        writeln!(f, "average fan out:         {:.2}", self.average_fan_out)?;
+        writeln!(f, "bytes used:              {}", self.bytes_used)?;
+        writeln!(
+            f,
+            "fragmentation:           {:.1}%",
+            self.fragmentation * 100.0
+        )?;
+        write!(f, "depth histogram:")?;
+        for (level, count) in self.depth_histogram.iter().enumerate() {
+            if *count > 0 {
+                write!(f, "\n  {level:>3}: {count}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use super::{
+    error::ValidationError,
+    point::{Point, PointData},
+    Branch, BranchKey, Octree,
+};
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Checks that the structure of the tree is internally consistent, this should always be the case
+    /// and is mainly useful for testing or checking trees loaded from elsewhere.
+    ///
+    /// # Errors
+    /// Returns the first broken invariant found.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut seen = vec![false; self.slab_end];
+        let mut reached = 0;
+        if let Some(root) = self.root {
+            self.check_key(root, None, &mut seen)?;
+            self.validate_branch(root, &PointData::ZERO, 0, &mut seen, &mut reached)?;
+        }
+
+        if reached == self.branches.len() {
+            Ok(())
+        } else {
+            Err(ValidationError::Orphaned {
+                count: self.branches.len() - reached,
+            })
+        }
+    }
+
+    /// Checks that `branch` exists and hasn't been reached before.
+    fn check_key(
+        &self,
+        branch: BranchKey,
+        parent: Option<usize>,
+        seen: &mut [bool],
+    ) -> Result<(), ValidationError> {
+        let key = u32::from(branch.0) as usize;
+        if !self.branches.contains(key) {
+            Err(ValidationError::MissingChild { branch: parent })
+        } else if std::mem::replace(&mut seen[key], true) {
+            Err(ValidationError::SharedChild { branch: key })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Validates `branch` given that `point` is in its cell which shares the first `depth` bits, returning
+    /// the number of items below it
+    #[allow(clippy::too_many_lines)]
+    fn validate_branch(
+        &self,
+        branch: BranchKey,
+        point: &PointData<P>,
+        depth: u8,
+        seen: &mut [bool],
+        reached: &mut usize,
+    ) -> Result<u32, ValidationError> {
+        let key = u32::from(branch.0) as usize;
+        *reached += 1;
+        match self.get_branch(branch) {
+            Branch::Split {
+                children,
+                occupied,
+                depth: split_depth,
+                len,
+                ..
+            } => {
+                let flags = (0_u8..8)
+                    .filter(|i| children[*i as usize].is_some())
+                    .fold(0_u8, |flags, i| flags | 1 << i);
+                if flags != *occupied || occupied.count_ones() < 2 {
+                    return Err(ValidationError::BadChildren { branch: key });
+                }
+                if *split_depth != depth + 1 || *split_depth > P::MAX_DEPTH {
+                    return Err(ValidationError::BadDepth { branch: key });
+                }
+
+                let mut total = 0;
+                for i in 0_u8..8 {
+                    if let Some(child) = children[i as usize] {
+                        self.check_key(child, Some(key), seen)?;
+                        let child_point = point.combine_ind(i, *split_depth);
+                        total +=
+                            self.validate_branch(child, &child_point, *split_depth, seen, reached)?;
+                    }
+                }
+                if total != *len {
+                    return Err(ValidationError::BadLength { branch: key });
+                }
+                Ok(total)
+            }
+            Branch::Skip {
+                point: skip_point,
+                point_depth,
+                child,
+                len,
+            } => {
+                if *point_depth <= depth || *point_depth >= P::MAX_DEPTH {
+                    return Err(ValidationError::BadDepth { branch: key });
+                }
+                if (point ^ skip_point).leading_zeros() < depth {
+                    return Err(ValidationError::BadPoint { branch: key });
+                }
+                self.check_key(*child, Some(key), seen)?;
+                if !matches!(self.get_branch(*child), Branch::Split { .. }) {
+                    return Err(ValidationError::BadSkipChild { branch: key });
+                }
+                let total =
+                    self.validate_branch(*child, skip_point, *point_depth, seen, reached)?;
+                if total != *len {
+                    return Err(ValidationError::BadLength { branch: key });
+                }
+                Ok(total)
+            }
+            Branch::Leaf {
+                point: leaf_point,
+                child,
+                ..
+            } => {
+                if (point ^ leaf_point).leading_zeros() < depth {
+                    return Err(ValidationError::BadPoint { branch: key });
+                }
+                let mut total = 1;
+                let mut next = *child;
+                while let Some(duplicate) = next {
+                    self.check_key(duplicate, Some(key), seen)?;
+                    *reached += 1;
+                    total += 1;
+                    let Branch::Leaf {
+                        point: duplicate_point,
+                        child,
+                        ..
+                    } = self.get_branch(duplicate)
+                    else {
+                        return Err(ValidationError::BadPoint {
+                            branch: u32::from(duplicate.0) as usize,
+                        });
+                    };
+                    if duplicate_point != leaf_point {
+                        return Err(ValidationError::BadPoint {
+                            branch: u32::from(duplicate.0) as usize,
+                        });
+                    }
+                    next = *child;
+                }
+                Ok(total)
+            }
+            Branch::Bucket { items } => {
+                if items.is_empty() {
+                    return Err(ValidationError::BadChildren { branch: key });
+                }
+                if items
+                    .iter()
+                    .any(|(item_point, _)| (point ^ item_point).leading_zeros() < depth)
+                {
+                    return Err(ValidationError::BadPoint { branch: key });
+                }
+                Ok(items.len() as u32)
+            }
+        }
+    }
+}
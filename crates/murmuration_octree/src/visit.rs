@@ -0,0 +1,244 @@
+use super::{
+    aggregate::Aggregate,
+    point::{Point, PointData},
+    Branch, BranchKey, Octree,
+};
+
+/// The type of branch a [`NodeView`] is looking at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// A branch which splits its cell into 8 equal child cells, only some of which may be occupied.
+    Split,
+    /// A branch which skips over layers of the tree where nothing splits, so it has a single child.
+    Skip,
+    /// A single point in the tree with all the items stored there.
+    Leaf,
+    /// A group of items anywhere in its cell, used instead of leaves when the tree has a
+    /// [bucket size](Octree::set_bucket_size).
+    Bucket,
+}
+
+/// What [`Octree::visit`] should do after visiting a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VisitAction {
+    /// Visit this node's children.
+    Descend,
+    /// Don't visit this node's children, but carry on with the rest of the tree.
+    Skip,
+    /// Stop visiting the tree.
+    Stop,
+}
+
+/// A view of a single node (branch) of an [`Octree`], along with the cell of space it covers.
+pub struct NodeView<'a, D, P: Point, A = ()> {
+    octree: &'a Octree<D, P, A>,
+    branch: BranchKey,
+    point: PointData<P>, // A point in this node's cell
+    depth: u8,
+    level: usize,
+}
+
+impl<'a, D, P: Point, A> NodeView<'a, D, P, A> {
+    /// Creates a view of `branch` where `point` shares the first `depth` bits with every item below it.
+    fn new(
+        octree: &'a Octree<D, P, A>,
+        branch: BranchKey,
+        point: PointData<P>,
+        depth: u8,
+        level: usize,
+    ) -> Self {
+        let (point, depth) = match octree.get_branch(branch) {
+            Branch::Split { .. } | Branch::Bucket { .. } => (point, depth),
+            Branch::Skip {
+                point, point_depth, ..
+            } => (point.clone(), *point_depth),
+            Branch::Leaf { point, .. } => (point.clone(), P::MAX_DEPTH),
+        };
+        Self {
+            octree,
+            branch,
+            point,
+            depth,
+            level,
+        }
+    }
+
+    /// Returns what type of branch this is.
+    pub fn kind(&self) -> NodeKind {
+        match self.octree.get_branch(self.branch) {
+            Branch::Split { .. } => NodeKind::Split,
+            Branch::Skip { .. } => NodeKind::Skip,
+            Branch::Leaf { .. } => NodeKind::Leaf,
+            Branch::Bucket { .. } => NodeKind::Bucket,
+        }
+    }
+
+    /// Returns the number of leading bits (of each ordered coordinate) shared by everything in this
+    /// node's cell, so `0` is the whole space and [`Point::MAX_DEPTH`] is a single point.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Returns the number of nodes above this one in the tree (so the root is `0`).
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Returns the smallest and largest corners (inclusive) of the cell this node covers.
+    ///
+    /// Cells divide up the [ordered](crate::OrderedBinary::to_ordered) coordinates, so for floats the
+    /// corners of a cell may be infinite or NaN.
+    pub fn cell(&self) -> ([P::Data; 3], [P::Data; 3]) {
+        let (min, max) = self.cell_data();
+        (min.to_array(), max.to_array())
+    }
+
+    /// Like [`cell`](Self::cell), but returns the underlying `PointData`
+    pub(crate) fn cell_data(&self) -> (PointData<P>, PointData<P>) {
+        self.point.cell_bounds(self.depth)
+    }
+
+    /// Returns the underlying points of the items stored at this node (see [`points`](Self::points))
+    pub(crate) fn point_data(&self) -> impl Iterator<Item = (&'a PointData<P>, &'a D)> {
+        let octree = self.octree;
+        let (mut leaf, bucket) = match octree.get_branch(self.branch) {
+            Branch::Leaf { .. } => (Some(self.branch), &[][..]),
+            Branch::Bucket { items } => (None, &items[..]),
+            _ => (None, &[][..]),
+        };
+        let leaves = std::iter::from_fn(move || {
+            let Branch::Leaf {
+                point, data, child, ..
+            } = octree.get_branch(leaf?)
+            else {
+                unreachable!()
+            };
+            leaf = *child;
+            Some((point, data))
+        });
+        bucket
+            .iter()
+            .map(|(point, data)| (point, data))
+            .chain(leaves)
+    }
+
+    /// Returns the number of items below this node.
+    pub fn len(&self) -> usize {
+        self.octree.branch_len(self.branch) as usize
+    }
+
+    /// Returns `true` if there are no items below this node (which is never the case as empty
+    /// branches are removed from the tree).
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the items stored at this node if it is a [`Leaf`](NodeKind::Leaf) (which are all at the
+    /// same point) or a [`Bucket`](NodeKind::Bucket), otherwise this will be empty.
+    pub fn items(&self) -> impl Iterator<Item = &'a D> {
+        self.points().map(|(_, data)| data)
+    }
+
+    /// Like [`items`](Self::items), but also returns the point of each item.
+    pub fn points(&self) -> impl Iterator<Item = ([P::Data; 3], &'a D)> {
+        self.point_data()
+            .map(|(point, data)| (point.to_array(), data))
+    }
+
+    /// Returns the index (from `0..8`) of each occupied child cell if this is a
+    /// [`Split`](NodeKind::Split) (as bitflags).
+    pub fn occupied(&self) -> u8 {
+        match self.octree.get_branch(self.branch) {
+            Branch::Split { occupied, .. } => *occupied,
+            _ => 0,
+        }
+    }
+
+    /// Returns views of this node's children.
+    pub fn children(&self) -> impl Iterator<Item = NodeView<'a, D, P, A>> + '_ {
+        let (children, split_depth) = match self.octree.get_branch(self.branch) {
+            Branch::Split {
+                children, depth, ..
+            } => (*children, Some(*depth)),
+            Branch::Skip { child, .. } => {
+                let mut children = [None; 8];
+                children[0] = Some(*child);
+                (children, None)
+            }
+            Branch::Leaf { .. } | Branch::Bucket { .. } => ([None; 8], None),
+        };
+        (0..8).filter_map(move |i| {
+            let child = children[i as usize]?;
+            Some(if let Some(depth) = split_depth {
+                let point = self.point.combine_ind(i, depth);
+                NodeView::new(self.octree, child, point, depth, self.level + 1)
+            } else {
+                let point = self.point.clone();
+                NodeView::new(self.octree, child, point, self.depth, self.level + 1)
+            })
+        })
+    }
+}
+
+impl<D, P: Point, A: Aggregate<D, P>> NodeView<'_, D, P, A> {
+    /// Returns the aggregate of all the items below this node.
+    pub fn aggregate(&self) -> A {
+        self.octree.branch_aggregate(self.branch)
+    }
+}
+
+impl<D, P: Point, A> Octree<D, P, A> {
+    /// Returns a view of the root node of the tree or `None` if it is empty, for traversing it manually.
+    ///
+    /// See also [`visit`](Self::visit).
+    pub fn root_node(&self) -> Option<NodeView<'_, D, P, A>> {
+        self.root
+            .map(|root| NodeView::new(self, root, PointData::ZERO, 0, 0))
+    }
+
+    /// Visits the nodes of the tree depth first (in [Morton order](https://en.wikipedia.org/wiki/Z-order_curve)),
+    /// where `visitor` chooses whether to look at the children of each node.
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::{NodeKind, Octree, VisitAction};
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[1_u32, 2, 3], "a");
+    /// tree.add(&[100, 2, 3], "b");
+    ///
+    /// // Find every leaf with an x coordinate below 50
+    /// let mut found = Vec::new();
+    /// tree.visit(|node| {
+    ///     let (min, _) = node.cell();
+    ///     if min[0] >= 50 {
+    ///         VisitAction::Skip
+    ///     } else {
+    ///         if node.kind() == NodeKind::Leaf {
+    ///             found.extend(node.items().copied());
+    ///         }
+    ///         VisitAction::Descend
+    ///     }
+    /// });
+    /// assert_eq!(found, ["a"]);
+    /// ```
+    pub fn visit<'a>(&'a self, mut visitor: impl FnMut(&NodeView<'a, D, P, A>) -> VisitAction) {
+        if let Some(root) = self.root_node() {
+            Self::visit_node(&root, &mut visitor);
+        }
+    }
+
+    /// Returns `false` if the visitor asked to stop
+    fn visit_node<'a>(
+        node: &NodeView<'a, D, P, A>,
+        visitor: &mut impl FnMut(&NodeView<'a, D, P, A>) -> VisitAction,
+    ) -> bool {
+        match visitor(node) {
+            VisitAction::Descend => node
+                .children()
+                .all(|child| Self::visit_node(&child, visitor)),
+            VisitAction::Skip => true,
+            VisitAction::Stop => false,
+        }
+    }
+}
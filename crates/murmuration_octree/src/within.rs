@@ -1,21 +1,33 @@
-use std::collections::VecDeque;
 use std::iter::FusedIterator;
 
 use super::{
+    path::Path,
     point::{ordered::OrderedBinary, Point, PointData},
+    region::{Ellipsoid, Region, Sphere},
     Branch, BranchKey, Octree,
 };
 
-struct Within<'a, D, P: Point> {
-    octree: &'a Octree<D, P>,
-    centre: PointData<P>,
-    sqr_dist: P::Data,
-    parents: VecDeque<(BranchKey, Option<u8>)>,
+struct Within<'a, D, P: Point, A, R> {
+    octree: &'a Octree<D, P, A>,
+    region: R,
+    parents: Path<(BranchKey, Option<u8>)>, // Splits also have the children left to look at
     leaf: Option<BranchKey>,
+    bucket: &'a [(PointData<P>, D)], // The rest of the bucket we are in the middle of
     point: PointData<P>, // The last point value we got from a Skip parent (plus info from Split)
 }
 
-impl<'a, D, P: Point> Iterator for Within<'a, D, P> {
+impl<'a, D, P: Point, A, R: Region<P>> Within<'a, D, P, A, R> {
+    /// Returns the next item in `bucket` in range, keeping the rest for later
+    fn next_in_bucket(&mut self, bucket: &'a [(PointData<P>, D)]) -> Option<&'a D> {
+        let found = bucket
+            .iter()
+            .position(|(point, _)| self.region.contains(point))?;
+        self.bucket = &bucket[found + 1..];
+        Some(&bucket[found].1)
+    }
+}
+
+impl<'a, D, P: Point, A, R: Region<P>> Iterator for Within<'a, D, P, A, R> {
     type Item = &'a D;
     fn next(&mut self) -> Option<&'a D> {
         if let Some(leaf) = self.leaf {
@@ -31,48 +43,54 @@ impl<'a, D, P: Point> Iterator for Within<'a, D, P> {
             }
             self.leaf = None;
         }
+        let bucket = std::mem::take(&mut self.bucket);
+        if let Some(data) = self.next_in_bucket(bucket) {
+            return Some(data);
+        }
 
         let mut moving_up = false;
-        'outer: loop {
-            if self.parents.is_empty() {
-                return None;
-            }
-            match self.octree.get_branch(self.parents[0].0) {
+        loop {
+            let (branch, remaining) = self.parents.pop()?;
+            match self.octree.get_branch(branch) {
                 Branch::Split {
-                    children, depth, ..
+                    children,
+                    occupied,
+                    depth,
+                    ..
                 } => {
-                    for i in self.parents[0].1.map_or(0, |n| n + 1)..8 {
-                        if let Some(child) = children[i as usize] {
-                            let closest = self.point.closest_distance(i, &self.centre, *depth);
-                            if closest <= self.sqr_dist || closest.is_irrelevant() {
-                                self.parents[0].1 = Some(i);
-                                self.parents.push_front((child, None));
-                                moving_up = false;
-                                if depth != &P::MAX_DEPTH {
-                                    self.point = self.point.combine_ind(i, *depth);
-                                }
-                                continue 'outer;
-                            }
-                        }
+                    let remaining = remaining.unwrap_or_else(|| {
+                        occupied & self.region.overlapping_children(&self.point, *depth)
+                    });
+                    if remaining != 0 {
+                        let i = remaining.trailing_zeros() as u8;
+                        // SAFETY: `occupied` says which children exist (see `validate`)
+                        let child = unsafe { children[i as usize].unwrap_unchecked() };
+                        self.parents
+                            .push((branch, Some(remaining & (remaining - 1))));
+                        self.parents.push((child, None));
+                        moving_up = false;
+                        self.point = self.point.combine_ind(i, *depth);
+                        continue;
                     }
                     // There are no more valid branches in here
                     moving_up = true;
-                    self.parents.pop_front();
                 }
                 Branch::Leaf { data, point, .. } => {
-                    if point.distance_squared(&self.centre) <= self.sqr_dist {
-                        self.leaf = Some(self.parents[0].0);
-                        self.parents.pop_front();
+                    if self.region.contains(point) {
+                        self.leaf = Some(branch);
+                        return Some(data);
+                    }
+                }
+                Branch::Bucket { items } => {
+                    if let Some(data) = self.next_in_bucket(items) {
                         return Some(data);
                     }
-                    self.parents.pop_front();
                 }
                 Branch::Skip { point, child, .. } => {
-                    if moving_up {
-                        self.parents.pop_front();
-                    } else {
+                    if !moving_up {
                         self.point = point.clone();
-                        self.parents.push_front((*child, None));
+                        self.parents.push((branch, None));
+                        self.parents.push((*child, None));
                     }
                 }
             }
@@ -80,22 +98,57 @@ impl<'a, D, P: Point> Iterator for Within<'a, D, P> {
     }
 }
 
-impl<D, P: Point> FusedIterator for Within<'_, D, P> {}
+impl<D, P: Point, A, R: Region<P>> FusedIterator for Within<'_, D, P, A, R> {}
 
-impl<D, P: Point> Octree<D, P> {
+impl<D, P: Point, A> Octree<D, P, A> {
     /// Returns all items within `distance` of `point`, in an unspecified order.
+    #[allow(clippy::needless_pass_by_value)]
     pub fn within(&self, point: &P, distance: P::Data) -> impl Iterator<Item = &D> {
-        let root = self
-            .root
-            .into_iter()
-            .map(|v| (v, None))
-            .collect::<VecDeque<_>>();
+        self.within_region(Sphere {
+            centre: point.get_point(),
+            sqr_dist: distance.distance_squared(&P::Data::ZERO),
+        })
+    }
+
+    /// Returns all items inside the ellipsoid around `point` with the given radius along each
+    /// axis, in an unspecified order.
+    ///
+    /// This is useful when distance along some axes matters less than others, such as a range which
+    /// is much shorter vertically than horizontally. All the radii should be more than zero.
+    ///
+    /// To avoid dividing, the squared distance along each axis is multiplied by the other two
+    /// squared radii, so with integer coordinates this is only exact while those products fit in
+    /// the coordinate type (and counts more points when they saturate).
+    ///
+    /// # Example
+    /// ```
+    /// use murmuration_octree::Octree;
+    ///
+    /// let mut tree = Octree::new();
+    /// tree.add(&[15.0_f32, 0.0, 0.0], "beside");
+    /// tree.add(&[0.0, 5.0, 0.0], "above");
+    /// tree.add(&[8.0, 2.0, 8.0], "near");
+    /// let found = tree.within_scaled(&[0.0, 0.0, 0.0], [20.0, 3.0, 20.0]);
+    /// let mut found = found.copied().collect::<Vec<_>>();
+    /// found.sort_unstable();
+    /// assert_eq!(found, ["beside", "near"]);
+    /// ```
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn within_scaled(&self, point: &P, radii: [P::Data; 3]) -> impl Iterator<Item = &D> {
+        self.within_region(Ellipsoid::new(point.get_point(), &radii))
+    }
+
+    fn within_region<R: Region<P>>(&self, region: R) -> Within<'_, D, P, A, R> {
+        let mut parents = Path::new();
+        if let Some(root) = self.root {
+            parents.push((root, None));
+        }
         Within {
             octree: self,
-            centre: point.get_point(),
-            sqr_dist: distance.clone() * distance,
-            parents: root,
+            region,
+            parents,
             leaf: None,
+            bucket: &[],
             point: PointData::<P>::ZERO,
         }
     }
@@ -0,0 +1,110 @@
+//! Checks that aggregates stay equal to a fold over the items as the tree is changed.
+use murmuration_octree::{Aggregate, Octree, VisitAction};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Counts the items and sums their ids and points.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Sum {
+    count: usize,
+    ids: u64,
+    points: [i64; 3],
+}
+
+impl Aggregate<u32, [i32; 3]> for Sum {
+    fn from_item(point: [i32; 3], id: &u32) -> Self {
+        Sum {
+            count: 1,
+            ids: u64::from(*id),
+            points: point.map(i64::from),
+        }
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Sum {
+            count: self.count + other.count,
+            ids: self.ids + other.ids,
+            points: [0, 1, 2].map(|i| self.points[i] + other.points[i]),
+        }
+    }
+}
+
+/// Sums the items by brute force, starting from the default
+fn fold<'a>(items: impl Iterator<Item = &'a ([i32; 3], u32)>) -> Sum {
+    items.fold(Sum::default(), |sum, (point, id)| {
+        sum.combine(&Sum::from_item(*point, id))
+    })
+}
+
+/// Checks the aggregate of the whole tree and of every node against the items in its cell
+fn check(tree: &Octree<u32, [i32; 3], Sum>, items: &[([i32; 3], u32)]) {
+    assert_eq!(tree.aggregate(), fold(items.iter()));
+    tree.visit(|node| {
+        let (min, max) = node.cell();
+        let inside = items
+            .iter()
+            .filter(|(point, _)| (0..3).all(|i| min[i] <= point[i] && point[i] <= max[i]));
+        assert_eq!(node.aggregate(), fold(inside), "{:?} node", node.kind());
+        VisitAction::Descend
+    });
+}
+
+fn random_point(rng: &mut StdRng) -> [i32; 3] {
+    [(); 3].map(|()| rng.gen_range(-8..8))
+}
+
+fn aggregates_follow_changes(bucket_size: usize) {
+    let mut rng = StdRng::seed_from_u64(28);
+    let mut tree: Octree<u32, [i32; 3], Sum> = Octree::default();
+    tree.set_bucket_size(bucket_size);
+    let mut items = Vec::new();
+    check(&tree, &items);
+
+    // The small range puts several items at most points
+    for id in 0..600 {
+        let point = random_point(&mut rng);
+        tree.add(&point, id);
+        items.push((point, id));
+    }
+    check(&tree, &items);
+
+    for _ in 0..250 {
+        let (point, id) = items.swap_remove(rng.gen_range(0..items.len()));
+        assert!(tree.remove(&point, &id));
+    }
+    check(&tree, &items);
+
+    for _ in 0..250 {
+        let ind = rng.gen_range(0..items.len());
+        let (from, id) = items[ind];
+        // Sometimes onto another item's point, or back to where it already is
+        let to = match rng.gen_range(0..3) {
+            0 => items[rng.gen_range(0..items.len())].0,
+            1 => from,
+            _ => random_point(&mut rng),
+        };
+        assert!(tree.move_data(&from, &to, id));
+        items[ind].0 = to;
+    }
+    check(&tree, &items);
+
+    tree.update_data(|id| *id = *id * 3 + 1);
+    for (_, id) in &mut items {
+        *id = *id * 3 + 1;
+    }
+    check(&tree, &items);
+
+    while let Some((point, id)) = items.pop() {
+        assert!(tree.remove(&point, &id));
+    }
+    check(&tree, &items);
+}
+
+#[test]
+fn aggregates_follow_changes_in_leaves() {
+    aggregates_follow_changes(1);
+}
+
+#[test]
+fn aggregates_follow_changes_in_buckets() {
+    aggregates_follow_changes(6);
+}
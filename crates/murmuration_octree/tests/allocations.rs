@@ -0,0 +1,59 @@
+//! Checks that queries and removals don't allocate.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use murmuration_octree::Octree;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations made by `f` on this thread
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn queries_dont_allocate() {
+    let mut tree = Octree::new();
+    for i in 0..1000_u32 {
+        tree.add(&[i as f32, (i % 10) as f32, (i % 7) as f32], i);
+    }
+    // Duplicates so that removing goes along a chain of leaves
+    for i in 1000..1010 {
+        tree.add(&[5.0, 5.0, 5.0], i);
+    }
+
+    let count = allocations(|| {
+        assert_eq!(tree.within(&[500.0, 0.0, 3.0], 1.0).count(), 1);
+        assert_eq!(tree.get(&[5.0, 5.0, 5.0]).count(), 11);
+        assert_eq!(tree.count_within(&[0.0, 0.0, 0.0], 50.0), 60);
+    });
+    assert_eq!(count, 0, "queries allocated");
+
+    // Moves within the same cell and removals reuse the tree's storage
+    let count = allocations(|| {
+        assert!(tree.remove(&[5.0, 5.0, 5.0], &1004));
+        assert!(tree.remove(&[999.0, 9.0, 5.0], &999));
+        assert!(tree.move_data(&[5.0, 5.0, 5.0], &[5.0, 5.0, 5.5], 1009));
+    });
+    assert_eq!(count, 0, "removing allocated");
+}
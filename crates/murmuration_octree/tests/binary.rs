@@ -0,0 +1,203 @@
+//! Checks that trees survive a round trip through the binary format and that bad data is rejected.
+use murmuration_octree::{LoadError, NonFinitePolicy, Octree, SavedOctree};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+fn sorted<'a>(ids: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+    let mut ids: Vec<_> = ids.copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Builds a tree with duplicates, gaps in its storage and a parked item.
+fn churned_tree(bucket_size: usize) -> Octree<u32, [f32; 3]> {
+    let mut rng = StdRng::seed_from_u64(bucket_size as u64);
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    tree.set_bucket_size(bucket_size);
+    let mut items: Vec<([f32; 3], u32)> = Vec::new();
+    for id in 0..500 {
+        let point = if id % 10 == 0 && !items.is_empty() {
+            items[rng.gen_range(0..items.len())].0
+        } else {
+            [0; 3].map(|_| rng.gen_range(-10.0..10.0))
+        };
+        tree.add(&point, id);
+        items.push((point, id));
+    }
+    for (point, id) in items.iter().step_by(3) {
+        assert!(tree.remove(point, id));
+    }
+    tree.add(&[f32::NAN, 0.0, 0.0], 1000);
+    tree
+}
+
+#[test]
+fn round_trip() {
+    for bucket_size in [1, 8] {
+        let tree = churned_tree(bucket_size);
+        let loaded: Octree<u32, [f32; 3]> = Octree::from_bytes(&tree.to_bytes()).unwrap();
+
+        loaded.validate().unwrap();
+        assert_eq!(loaded.len(), tree.len());
+        assert_eq!(loaded.bucket_size(), bucket_size);
+        assert_eq!(loaded.non_finite_policy(), NonFinitePolicy::Park);
+        assert_eq!(sorted(loaded.parked()), [1000]);
+        for centre in [[0.0, 0.0, 0.0], [5.0, -5.0, 2.0], [-9.0, 9.0, -9.0]] {
+            assert_eq!(
+                sorted(loaded.within(&centre, 4.0)),
+                sorted(tree.within(&centre, 4.0))
+            );
+        }
+    }
+}
+
+#[test]
+fn empty_round_trip() {
+    let tree: Octree<u32, [i64; 3]> = Octree::new();
+    let loaded: Octree<u32, [i64; 3]> = Octree::from_bytes(&tree.to_bytes()).unwrap();
+    assert!(loaded.is_empty());
+}
+
+#[test]
+fn rejects_bad_data() {
+    let bytes = churned_tree(1).to_bytes();
+    let load = |bytes: &[u8]| Octree::<u32, [f32; 3]>::from_bytes(bytes).err();
+
+    assert_eq!(load(b"not a tree"), Some(LoadError::NotAnOctree));
+    assert_eq!(load(&bytes[..bytes.len() - 1]), Some(LoadError::Truncated));
+    assert_eq!(
+        Octree::<u32, [u32; 3]>::from_bytes(&bytes).err(),
+        Some(LoadError::WrongPointType)
+    );
+    // The same size of coordinate but a different type is told apart by its name
+    assert_eq!(
+        Octree::<u32, [i32; 3]>::from_bytes(&bytes).err(),
+        Some(LoadError::WrongPointType)
+    );
+    assert_eq!(
+        Octree::<u64, [f32; 3]>::from_bytes(&bytes).err(),
+        Some(LoadError::WrongDataType)
+    );
+    // As is data of the same size but a different type
+    assert_eq!(
+        Octree::<f32, [f32; 3]>::from_bytes(&bytes).err(),
+        Some(LoadError::WrongDataType)
+    );
+    assert_eq!(
+        Octree::<[u16; 2], [f32; 3]>::from_bytes(&bytes).err(),
+        Some(LoadError::WrongDataType)
+    );
+
+    let mut newer = bytes.clone();
+    newer[8] = 2;
+    assert_eq!(
+        load(&newer),
+        Some(LoadError::UnsupportedVersion { version: 2 })
+    );
+
+    // The byte after the version and max depth is the non-finite policy
+    let mut policy = bytes.clone();
+    policy[11] = 7;
+    assert_eq!(load(&policy), Some(LoadError::InvalidHeader));
+
+    // The node and item counts (after the bucket size) must fit in the `u32`s nodes use
+    for offset in [28, 36] {
+        let mut oversized = bytes.clone();
+        oversized[offset..offset + 8].copy_from_slice(&(u64::from(u32::MAX) + 1).to_le_bytes());
+        assert_eq!(load(&oversized), Some(LoadError::InvalidHeader));
+    }
+
+    // Corrupting any byte of the node table must give an error or a valid tree, never a panic
+    let nodes = 8 + 2 + 1 + 1 + 4 + 4 + 8 * 4 + 4 + "f32".len() + 4 + "u32".len();
+    for i in nodes..nodes + 16 * 40 {
+        let mut corrupt = bytes.clone();
+        corrupt[i] ^= 0x5a;
+        if let Ok(tree) = Octree::<u32, [f32; 3]>::from_bytes(&corrupt) {
+            tree.validate().unwrap();
+        }
+    }
+}
+
+#[test]
+fn saved_queries_match_tree() {
+    for bucket_size in [1, 8] {
+        let tree = churned_tree(bucket_size);
+        let bytes = tree.to_bytes();
+        let saved = SavedOctree::<u32, [f32; 3]>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(saved.len(), tree.len());
+        assert_eq!(saved.bucket_size(), bucket_size);
+        assert_eq!(saved.non_finite_policy(), NonFinitePolicy::Park);
+        assert_eq!(saved.parked().collect::<Vec<_>>(), [1000]);
+        let items: Vec<_> = saved.iter().collect();
+        assert_eq!(items.len(), tree.len() - 1);
+        for (point, id) in &items {
+            assert_eq!(saved.get(point).filter(|found| found == id).count(), 1);
+            assert_eq!(
+                sorted(saved.get(point).collect::<Vec<_>>().iter()),
+                sorted(tree.get(point))
+            );
+        }
+        assert_eq!(saved.get_single(&[20.0, 0.0, 0.0]), None);
+
+        for centre in [[0.0, 0.0, 0.0], [5.0, -5.0, 2.0], [-9.0, 9.0, -9.0]] {
+            let within: Vec<_> = saved.within(&centre, 4.0).collect();
+            assert_eq!(sorted(within.iter()), sorted(tree.within(&centre, 4.0)));
+            assert_eq!(saved.count_within(&centre, 4.0), within.len());
+
+            let max = centre.map(|n| n + 3.0);
+            let in_aabb: Vec<_> = saved.in_aabb(&centre, &max).collect();
+            let expected = items
+                .iter()
+                .filter(|(point, _)| (0..3).all(|i| centre[i] <= point[i] && point[i] <= max[i]));
+            assert_eq!(sorted(in_aabb.iter()), sorted(expected.map(|(_, id)| id)));
+            assert_eq!(tree.count_in_aabb(&centre, &max), in_aabb.len());
+            assert_eq!(saved.count_in_aabb(&centre, &max), in_aabb.len());
+        }
+    }
+
+    let tree: Octree<u32, [i64; 3]> = Octree::new();
+    let bytes = tree.to_bytes();
+    let saved = SavedOctree::<u32, [i64; 3]>::from_bytes(&bytes).unwrap();
+    assert!(saved.is_empty());
+    assert_eq!(saved.within(&[0, 0, 0], i64::MAX).count(), 0);
+    assert_eq!(saved.get_single(&[0, 0, 0]), None);
+}
+
+#[test]
+fn saved_rejects_bad_data() {
+    let bytes = churned_tree(8).to_bytes();
+    let load = |bytes: &[u8]| SavedOctree::<u32, [f32; 3]>::from_bytes(bytes).err();
+    assert_eq!(load(b"not a tree"), Some(LoadError::NotAnOctree));
+    assert_eq!(load(&bytes[..bytes.len() - 1]), Some(LoadError::Truncated));
+    assert_eq!(
+        SavedOctree::<u32, [i32; 3]>::from_bytes(&bytes).err(),
+        Some(LoadError::WrongPointType)
+    );
+    assert_eq!(
+        SavedOctree::<i32, [f32; 3]>::from_bytes(&bytes).err(),
+        Some(LoadError::WrongDataType)
+    );
+
+    // Corrupt node tables and points must be rejected or still give the same answers as a loaded
+    // tree, never panic
+    let nodes = 8 + 2 + 1 + 1 + 4 + 4 + 8 * 4 + 4 + "f32".len() + 4 + "u32".len();
+    for i in (nodes..bytes.len()).step_by(5) {
+        let mut corrupt = bytes.clone();
+        corrupt[i] ^= 0x5a;
+        let Ok(saved) = SavedOctree::<u32, [f32; 3]>::from_bytes(&corrupt) else {
+            continue;
+        };
+        let centre = [1.0, -2.0, 3.0];
+        let within: Vec<_> = saved.within(&centre, 5.0).collect();
+        assert_eq!(saved.count_within(&centre, 5.0), within.len());
+        if let Ok(tree) = Octree::<u32, [f32; 3]>::from_bytes(&corrupt) {
+            assert_eq!(sorted(within.iter()), sorted(tree.within(&centre, 5.0)));
+            for (point, _) in saved.iter() {
+                assert_eq!(
+                    sorted(saved.get(&point).collect::<Vec<_>>().iter()),
+                    sorted(tree.get(&point))
+                );
+            }
+        }
+    }
+}
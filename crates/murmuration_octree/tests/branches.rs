@@ -0,0 +1,70 @@
+//! Checks that every branch stays reachable as branches are freed, their slots reused and the
+//! tree compacted, since branch lookups are unchecked.
+use murmuration_octree::Octree;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+fn sorted(mut ids: Vec<u32>) -> Vec<u32> {
+    ids.sort_unstable();
+    ids
+}
+
+/// Checks every item can be found at its point and by a query covering the whole tree
+fn check(tree: &Octree<u32, [i32; 3]>, items: &[([i32; 3], u32)]) {
+    tree.validate().unwrap();
+    assert_eq!(tree.len(), items.len());
+    for (point, id) in items {
+        assert!(
+            tree.get(point).any(|found| found == id),
+            "{id} at {point:?}"
+        );
+    }
+    let all = sorted(tree.within(&[0, 0, 0], i32::MAX).copied().collect());
+    assert_eq!(all, sorted(items.iter().map(|(_, id)| *id).collect()));
+}
+
+#[test]
+fn freed_branches_are_reused() {
+    let mut rng = StdRng::seed_from_u64(31);
+    let mut tree = Octree::new();
+    let mut items = Vec::new();
+    let mut next_id = 0;
+
+    for round in 0..20 {
+        // Grow the tree, then empty most of it so that whole subtrees are freed
+        for _ in 0..200 {
+            let point = [(); 3].map(|()| rng.gen_range(-1000..1000));
+            tree.add(&point, next_id);
+            items.push((point, next_id));
+            next_id += 1;
+        }
+        check(&tree, &items);
+
+        let keep = if round % 2 == 0 { 10 } else { 150 };
+        while items.len() > keep {
+            let (point, id) = items.swap_remove(rng.gen_range(0..items.len()));
+            assert!(tree.remove(&point, &id));
+        }
+        check(&tree, &items);
+
+        // Compacting renumbers every branch, and later rounds allocate around the new layout
+        match round % 3 {
+            0 => tree.compact(),
+            1 => tree.shrink_to_fit(),
+            _ => {}
+        }
+        check(&tree, &items);
+
+        // Moving items splits and collapses branches using the freed slots
+        for item in items.iter_mut().take(5) {
+            let point = [(); 3].map(|()| rng.gen_range(-10..10));
+            assert!(tree.move_data(&item.0, &point, item.1));
+            item.0 = point;
+        }
+        check(&tree, &items);
+    }
+
+    while let Some((point, id)) = items.pop() {
+        assert!(tree.remove(&point, &id));
+    }
+    check(&tree, &items);
+}
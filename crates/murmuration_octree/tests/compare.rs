@@ -0,0 +1,139 @@
+//! Checks cloning, equality and diffing of octrees.
+use murmuration_octree::{NonFinitePolicy, Octree};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+fn random_items(rng: &mut StdRng, count: u32) -> Vec<([i16; 3], u32)> {
+    (0..count)
+        .map(|id| ([0; 3].map(|_| rng.gen_range(-20..20)), id))
+        .collect()
+}
+
+#[test]
+fn equality_ignores_layout() {
+    let mut rng = StdRng::seed_from_u64(41);
+    let items = random_items(&mut rng, 500);
+
+    let mut tree = Octree::new();
+    for (point, id) in &items {
+        tree.add(point, *id);
+    }
+    let mut bucketed = Octree::with_bucket_size(8);
+    for (point, id) in items.iter().rev() {
+        bucketed.add(point, *id);
+    }
+    assert!(tree == bucketed);
+
+    let mut cloned = tree.clone();
+    assert!(cloned == tree);
+    cloned.move_data(&items[3].0, &[100, 100, 100], items[3].1);
+    assert!(cloned != tree);
+    cloned.move_data(&[100, 100, 100], &items[3].0, items[3].1);
+    assert!(cloned == tree);
+
+    // The same data at swapped points isn't equal
+    cloned.move_data(&items[0].0, &items[1].0, items[0].1);
+    cloned.move_data(&items[1].0, &items[0].0, items[1].1);
+    assert_eq!(cloned == tree, items[0].0 == items[1].0);
+
+    assert!(Octree::<u32, [i16; 3]>::new() == Octree::new());
+}
+
+#[test]
+fn equality_counts_stacked_data() {
+    // Thousands of items at one point, added in different orders
+    let mut tree = Octree::new();
+    let mut other = Octree::with_bucket_size(16);
+    for id in 0..4000_u32 {
+        tree.add(&[1, 2, 3], id % 1000);
+        other.add(&[1, 2, 3], 999 - id % 1000);
+    }
+    tree.add(&[4, 5, 6], 7);
+    other.add(&[4, 5, 6], 7);
+    assert!(tree == other);
+
+    // The same data with different counts isn't equal
+    other.remove(&[1, 2, 3], &5);
+    other.add(&[1, 2, 3], 6);
+    assert!(tree != other);
+    other.remove(&[1, 2, 3], &6);
+    other.add(&[1, 2, 3], 5);
+    assert!(tree == other);
+    other.move_data(&[4, 5, 6], &[4, 5, 7], 7);
+    assert!(tree != other);
+}
+
+#[test]
+fn equality_includes_parked() {
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    tree.add(&[f32::NAN, 0.0, 0.0], 1);
+    tree.add(&[1.0, 2.0, 3.0], 2);
+    let mut other = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    other.add(&[1.0, 2.0, 3.0], 2);
+    assert!(tree != other);
+    other.add(&[f32::NAN, 0.0, 0.0], 1);
+    assert!(tree == other);
+    other.move_data(&[f32::NAN, 0.0, 0.0], &[f32::INFINITY, 0.0, 0.0], 1);
+    assert!(tree != other);
+}
+
+#[test]
+fn diff_matches_changes() {
+    let mut rng = StdRng::seed_from_u64(410);
+    let items = random_items(&mut rng, 300);
+    let mut old = Octree::with_bucket_size(4);
+    for (point, id) in &items {
+        old.add(point, *id);
+    }
+    assert!(old.diff(&old.clone()).is_empty());
+
+    let mut new = old.clone();
+    let (mut added, mut removed, mut moved) = (Vec::new(), Vec::new(), Vec::new());
+    for (point, id) in &items {
+        match rng.gen_range(0..10) {
+            0 => {
+                new.remove(point, id);
+                removed.push(*id);
+            }
+            1 => {
+                let to = [0; 3].map(|_| rng.gen_range(-20..20));
+                new.move_data(point, &to, *id);
+                if &to != point {
+                    moved.push((*point, to, *id));
+                }
+            }
+            _ => {}
+        }
+    }
+    for id in 300..320 {
+        new.add(&[id as i16, 0, 0], id);
+        added.push(id);
+    }
+
+    let diff = old.diff(&new);
+    let mut diff_added = diff.added.iter().map(|(_, id)| **id).collect::<Vec<_>>();
+    let mut diff_removed = diff.removed.iter().map(|(_, id)| **id).collect::<Vec<_>>();
+    let mut diff_moved = diff
+        .moved
+        .iter()
+        .map(|(from, to, id)| (*from, *to, **id))
+        .collect::<Vec<_>>();
+    diff_added.sort_unstable();
+    diff_removed.sort_unstable();
+    diff_moved.sort_unstable_by_key(|(_, _, id)| *id);
+    assert_eq!(diff_added, added);
+    assert_eq!(diff_removed, removed);
+    assert_eq!(diff_moved, moved);
+
+    // Applying the diff to the old tree gives the new one
+    let mut patched = old.clone();
+    for (point, id) in &diff.removed {
+        assert!(patched.remove(point, id));
+    }
+    for (from, to, id) in &diff.moved {
+        assert!(patched.move_data(from, to, **id));
+    }
+    for (point, id) in &diff.added {
+        patched.add(point, **id);
+    }
+    assert!(patched == new);
+}
@@ -0,0 +1,556 @@
+//! Randomised tests comparing the octree against a brute force list of items.
+use murmuration_octree::{Aggregate, NonFinitePolicy, Octree, OrderedBinary};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::fmt::Debug;
+
+/// Counts the items and sums their ids.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Totals {
+    count: usize,
+    ids: u64,
+}
+
+impl<N: OrderedBinary> Aggregate<u32, [N; 3]> for Totals {
+    fn from_item(_point: [N; 3], id: &u32) -> Self {
+        Totals {
+            count: 1,
+            ids: u64::from(*id),
+        }
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Totals {
+            count: self.count + other.count,
+            ids: self.ids + other.ids,
+        }
+    }
+}
+
+fn sqr_dist<N: OrderedBinary>(a: &[N; 3], b: &[N; 3]) -> N {
+    a[0].distance_squared(&b[0]) + a[1].distance_squared(&b[1]) + a[2].distance_squared(&b[2])
+}
+
+fn sorted(mut ids: Vec<u32>) -> Vec<u32> {
+    ids.sort_unstable();
+    ids
+}
+
+/// Runs a random sequence of adds, removes and moves, checking the tree against a `Vec` after each.
+fn differential<N>(
+    seed: u64,
+    steps: usize,
+    bucket_size: usize,
+    coord: impl Fn(&mut StdRng) -> N,
+    radius: impl Fn(&mut StdRng) -> N,
+) where
+    N: OrderedBinary + Copy + Debug,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut tree: Octree<u32, [N; 3], Totals> = Octree::default();
+    tree.set_bucket_size(bucket_size);
+    let mut items: Vec<([N; 3], u32)> = Vec::new();
+    let mut next_id = 0;
+
+    let random_point = |rng: &mut StdRng, items: &[([N; 3], u32)]| {
+        // Reuse existing points sometimes so that there are plenty of duplicates
+        if !items.is_empty() && rng.gen_bool(0.2) {
+            items[rng.gen_range(0..items.len())].0
+        } else {
+            [coord(rng), coord(rng), coord(rng)]
+        }
+    };
+
+    for step in 0..steps {
+        match rng.gen_range(0..10) {
+            0..=4 => {
+                let point = random_point(&mut rng, &items);
+                tree.add(&point, next_id);
+                items.push((point, next_id));
+                next_id += 1;
+            }
+            5..=6 if !items.is_empty() => {
+                let (point, id) = items.swap_remove(rng.gen_range(0..items.len()));
+                assert!(
+                    tree.remove(&point, &id),
+                    "step {step}: failed to remove {id}"
+                );
+                assert!(!tree.remove(&point, &id), "step {step}: removed {id} twice");
+            }
+            _ if !items.is_empty() => {
+                let ind = rng.gen_range(0..items.len());
+                let new_point = random_point(&mut rng, &items);
+                let (point, id) = items[ind];
+                assert!(
+                    tree.move_data(&point, &new_point, id),
+                    "step {step}: failed to move {id}"
+                );
+                items[ind].0 = new_point;
+            }
+            _ => {}
+        }
+        if step % 50 == 49 {
+            tree.compact();
+            assert_eq!(tree.fragmentation(), 0.0, "step {step}: compact");
+        }
+
+        if let Err(err) = tree.validate() {
+            panic!("step {step}: {err}\n{tree:?}");
+        }
+        assert_eq!(tree.len(), items.len(), "step {step}");
+        assert_eq!(
+            tree.aggregate(),
+            Totals {
+                count: items.len(),
+                ids: items.iter().map(|(_, id)| u64::from(*id)).sum(),
+            },
+            "step {step}"
+        );
+
+        let centre = random_point(&mut rng, &items);
+        let expected = sorted(
+            items
+                .iter()
+                .filter(|(point, _)| *point == centre)
+                .map(|(_, id)| *id)
+                .collect(),
+        );
+        assert_eq!(
+            sorted(tree.get(&centre).copied().collect()),
+            expected,
+            "step {step}: get"
+        );
+
+        let distance = radius(&mut rng);
+        let sqr = distance * distance;
+        let expected = sorted(
+            items
+                .iter()
+                .filter(|(point, _)| sqr_dist(point, &centre) <= sqr)
+                .map(|(_, id)| *id)
+                .collect(),
+        );
+        assert_eq!(
+            sorted(tree.within(&centre, distance).copied().collect()),
+            expected,
+            "step {step}: within {centre:?} {distance:?}"
+        );
+        assert_eq!(
+            tree.count_within(&centre, distance),
+            expected.len(),
+            "step {step}"
+        );
+
+        let nearest = tree.nearest(&centre).map(|id| {
+            let (point, _) = items.iter().find(|(_, item)| item == id).unwrap();
+            sqr_dist(point, &centre)
+        });
+        let expected = items
+            .iter()
+            .map(|(point, _)| sqr_dist(point, &centre))
+            .reduce(|a, b| if b < a { b } else { a });
+        assert_eq!(nearest, expected, "step {step}: nearest {centre:?}");
+        let approx = tree.nearest_approx(&centre, N::ZERO).map(|id| {
+            let (point, _) = items.iter().find(|(_, item)| item == id).unwrap();
+            sqr_dist(point, &centre)
+        });
+        assert_eq!(approx, expected, "step {step}: nearest_approx {centre:?}");
+
+        let farthest = tree.farthest(&centre).map(|id| {
+            let (point, _) = items.iter().find(|(_, item)| item == id).unwrap();
+            sqr_dist(point, &centre)
+        });
+        let expected = items
+            .iter()
+            .map(|(point, _)| sqr_dist(point, &centre))
+            .reduce(|a, b| if b > a { b } else { a });
+        assert_eq!(farthest, expected, "step {step}: farthest {centre:?}");
+        let bounds = tree.bounds();
+        assert_eq!(bounds.is_some(), !items.is_empty(), "step {step}: bounds");
+        for axis in 0..=2 {
+            let coords = || items.iter().map(|(point, _)| point[axis]);
+            let min = coords().reduce(|a, b| if b < a { b } else { a });
+            let max = coords().reduce(|a, b| if b > a { b } else { a });
+            assert_eq!(
+                bounds.map(|(low, high)| (low[axis], high[axis])),
+                min.zip(max),
+                "step {step}: bounds"
+            );
+            assert_eq!(
+                tree.min_by_axis(axis).map(|(p, _)| p[axis]),
+                min,
+                "step {step}"
+            );
+            assert_eq!(
+                tree.max_by_axis(axis).map(|(p, _)| p[axis]),
+                max,
+                "step {step}"
+            );
+        }
+
+        let other = random_point(&mut rng, &items);
+        let min = [0, 1, 2].map(|i| {
+            if centre[i] < other[i] {
+                centre[i]
+            } else {
+                other[i]
+            }
+        });
+        let max = [0, 1, 2].map(|i| {
+            if centre[i] < other[i] {
+                other[i]
+            } else {
+                centre[i]
+            }
+        });
+        let expected = items
+            .iter()
+            .filter(|(point, _)| (0..3).all(|i| min[i] <= point[i] && point[i] <= max[i]))
+            .count();
+        assert_eq!(
+            tree.count_in_aabb(&min, &max),
+            expected,
+            "step {step}: count_in_aabb"
+        );
+    }
+
+    // Check the frozen tree gives the same answers
+    let frozen = tree.freeze();
+    assert_eq!(frozen.len(), items.len(), "frozen");
+    for _ in 0..50 {
+        let centre = random_point(&mut rng, &items);
+        let distance = radius(&mut rng);
+        let sqr = distance * distance;
+        let expected = sorted(
+            items
+                .iter()
+                .filter(|(point, _)| sqr_dist(point, &centre) <= sqr)
+                .map(|(_, id)| *id)
+                .collect(),
+        );
+        assert_eq!(
+            sorted(frozen.within(&centre, distance).copied().collect()),
+            expected,
+            "frozen: within {centre:?} {distance:?}"
+        );
+        assert_eq!(frozen.count_within(&centre, distance), expected.len());
+
+        let expected = sorted(
+            items
+                .iter()
+                .filter(|(point, _)| *point == centre)
+                .map(|(_, id)| *id)
+                .collect(),
+        );
+        assert_eq!(
+            sorted(frozen.get(&centre).copied().collect()),
+            expected,
+            "frozen: get"
+        );
+    }
+}
+
+#[test]
+fn differential_u16() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            1,
+            |rng| rng.gen_range(0_u16..100),
+            |rng| rng.gen_range(0..40),
+        );
+    }
+}
+
+#[test]
+fn differential_u32() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            1,
+            |rng| rng.gen_range(0_u32..64),
+            |rng| rng.gen_range(0..30),
+        );
+    }
+}
+
+#[test]
+fn differential_i32() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            1,
+            |rng| rng.gen_range(-50_i32..50),
+            |rng| rng.gen_range(0..40),
+        );
+    }
+}
+
+#[test]
+fn differential_i64() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            1,
+            |rng| rng.gen_range(-1000_i64..1000),
+            |rng| rng.gen_range(0..800),
+        );
+    }
+}
+
+#[test]
+fn differential_f32() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            1,
+            |rng| rng.gen_range(-10.0_f32..10.0),
+            |rng| rng.gen_range(0.0..8.0),
+        );
+    }
+}
+
+#[test]
+fn differential_f64() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            1,
+            |rng| rng.gen_range(-1e6_f64..1e6),
+            |rng| rng.gen_range(0.0..1e6),
+        );
+    }
+}
+
+#[test]
+fn differential_f32_clustered() {
+    // Very close points share long prefixes so this creates lots of skip branches
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            1,
+            |rng| 1.0 + f32::from(rng.gen_range(0_u8..8)) * f32::EPSILON,
+            |rng| f32::from(rng.gen_range(0_u8..4)) * f32::EPSILON,
+        );
+    }
+}
+
+#[test]
+fn differential_u32_buckets() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            4,
+            |rng| rng.gen_range(0_u32..64),
+            |rng| rng.gen_range(0..30),
+        );
+    }
+}
+
+#[test]
+fn differential_f32_buckets() {
+    for seed in 0..20 {
+        differential(
+            seed,
+            300,
+            16,
+            |rng| rng.gen_range(-10.0_f32..10.0),
+            |rng| rng.gen_range(0.0..8.0),
+        );
+    }
+}
+
+#[test]
+fn nearest_approx_within_epsilon() {
+    let mut rng = StdRng::seed_from_u64(45);
+    let mut tree = Octree::new();
+    let mut items = Vec::new();
+    for id in 0..5000_u32 {
+        let point = [0; 3].map(|_| rng.gen_range(-1000.0..1000.0_f64));
+        tree.add(&point, id);
+        items.push(point);
+    }
+    for epsilon in [0.0, 0.1, 0.5, 2.0] {
+        for _ in 0..200 {
+            let centre = [0; 3].map(|_| rng.gen_range(-1100.0..1100.0_f64));
+            let closest = items
+                .iter()
+                .map(|point| sqr_dist(point, &centre))
+                .fold(f64::INFINITY, f64::min);
+            let found = tree.nearest_approx(&centre, epsilon).unwrap();
+            let distance = sqr_dist(&items[*found as usize], &centre);
+            assert!(
+                distance.sqrt() <= closest.sqrt() * (1.0 + epsilon) + 1e-9,
+                "{centre:?} {epsilon}: {distance} vs {closest}"
+            );
+        }
+    }
+}
+
+#[test]
+fn nearest_skips_nan_distances() {
+    let mut rng = StdRng::seed_from_u64(46);
+    let mut tree = Octree::new();
+    // Added first so that it is the first item `nearest_approx` finds
+    tree.add(&[f32::NAN, 0.0, 0.0], 0);
+    tree.add(&[0.0, -f32::NAN, 0.0], 1);
+    let mut items = vec![[f32::NAN, 0.0, 0.0], [0.0, -f32::NAN, 0.0]];
+    assert_eq!(tree.nearest(&[0.0, 0.0, 0.0]), None);
+    assert_eq!(tree.nearest_approx(&[0.0, 0.0, 0.0], 0.0), None);
+
+    for id in 2..300_u32 {
+        let point = [0; 3].map(|_| match rng.gen_range(0..30) {
+            0 => f32::NAN,
+            1 => -f32::NAN,
+            2 => f32::INFINITY,
+            _ => rng.gen_range(-100.0..100.0),
+        });
+        tree.add(&point, id);
+        items.push(point);
+    }
+    for _ in 0..100 {
+        let centre = [0; 3].map(|_| rng.gen_range(-110.0..110.0_f32));
+        let mut expected: Vec<_> = items
+            .iter()
+            .map(|point| sqr_dist(point, &centre))
+            .filter(|distance| !distance.is_nan())
+            .collect();
+        expected.sort_by(f32::total_cmp);
+        let found: Vec<_> = tree
+            .nearest_iter(&centre)
+            .map(|id| sqr_dist(&items[*id as usize], &centre))
+            .collect();
+        assert_eq!(found, expected, "{centre:?}");
+
+        let approx = tree.nearest_approx(&centre, 0.0).unwrap();
+        assert_eq!(sqr_dist(&items[*approx as usize], &centre), expected[0]);
+    }
+}
+
+/// Checks `within_scaled` against the ellipsoid equation, with radii small enough that the
+/// cross-multiplied distances don't saturate
+fn within_scaled<N>(
+    seed: u64,
+    coord: impl Fn(&mut StdRng) -> N,
+    radius: impl Fn(&mut StdRng) -> N,
+    inside: impl Fn(&[N; 3], &[N; 3], &[N; 3]) -> bool,
+) where
+    N: OrderedBinary + Copy + Debug,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    for bucket_size in [1, 8] {
+        let mut tree = Octree::with_bucket_size(bucket_size);
+        let mut items = Vec::new();
+        for id in 0..1000_u32 {
+            let point = [coord(&mut rng), coord(&mut rng), coord(&mut rng)];
+            tree.add(&point, id);
+            items.push(point);
+        }
+        for _ in 0..200 {
+            let centre = [coord(&mut rng), coord(&mut rng), coord(&mut rng)];
+            let radii = [radius(&mut rng), radius(&mut rng), radius(&mut rng)];
+            let expected = (0..1000)
+                .filter(|id| inside(&items[*id as usize], &centre, &radii))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                sorted(tree.within_scaled(&centre, radii).copied().collect()),
+                expected,
+                "{centre:?} {radii:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn within_scaled_f64() {
+    within_scaled(
+        50,
+        |rng| rng.gen_range(-100.0_f64..100.0),
+        |rng| rng.gen_range(1.0..60.0),
+        |point, centre, radii| {
+            (0..3)
+                .map(|i| ((point[i] - centre[i]) / radii[i]).powi(2))
+                .sum::<f64>()
+                <= 1.0
+        },
+    );
+}
+
+#[test]
+fn within_scaled_i64() {
+    within_scaled(
+        500,
+        |rng| rng.gen_range(-100_i64..100),
+        |rng| rng.gen_range(1..60),
+        |point, centre, radii| {
+            let sqr = |n: i64| n * n;
+            let [rx, ry, rz] = radii.map(sqr);
+            sqr(point[0] - centre[0]) * ry * rz
+                + sqr(point[1] - centre[1]) * rx * rz
+                + sqr(point[2] - centre[2]) * rx * ry
+                <= rx * ry * rz
+        },
+    );
+}
+
+#[test]
+fn bounds_with_non_finite() {
+    let mut rng = StdRng::seed_from_u64(51);
+    for bucket_size in [1, 8] {
+        let mut tree = Octree::new();
+        tree.set_bucket_size(bucket_size);
+        let mut items = Vec::new();
+        for id in 0..300_u32 {
+            let point = [0; 3].map(|_| match rng.gen_range(0..20) {
+                0 => f32::NAN,
+                1 => f32::INFINITY,
+                2 => f32::NEG_INFINITY,
+                _ => rng.gen_range(-100.0..100.0),
+            });
+            tree.add(&point, id);
+            items.push(point);
+
+            // NaN coordinates are ignored, but infinities count
+            let (min, max) = tree.bounds().unwrap();
+            for axis in 0..=2 {
+                let coords = items
+                    .iter()
+                    .map(|point| point[axis])
+                    .filter(|n| !n.is_nan());
+                let expected_min = coords.clone().reduce(f32::min);
+                let expected_max = coords.reduce(f32::max);
+                match expected_min.zip(expected_max) {
+                    Some(expected) => assert_eq!((min[axis], max[axis]), expected, "id {id}"),
+                    None => assert!(min[axis].is_nan() && max[axis].is_nan(), "id {id}"),
+                }
+            }
+        }
+    }
+
+    // An axis with only NaN coordinates is NaN in both corners
+    let mut tree = Octree::new();
+    tree.add(&[f32::NAN, 1.0, f32::INFINITY], 0);
+    tree.add(&[f32::NAN, -2.0, 3.0], 1);
+    let (min, max) = tree.bounds().unwrap();
+    assert!(min[0].is_nan() && max[0].is_nan());
+    assert_eq!(
+        (&min[1..], &max[1..]),
+        (&[-2.0, 3.0][..], &[1.0, f32::INFINITY][..])
+    );
+
+    // Parked items aren't included, so a tree with only parked items has no bounds
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    tree.add(&[f32::NAN, 1.0, 2.0], 0);
+    tree.add(&[f32::INFINITY, 1.0, 2.0], 1);
+    assert_eq!(tree.bounds(), None);
+    tree.add(&[3.0, -1.0, 0.5], 2);
+    tree.add(&[-3.0, 1.0, 0.0], 3);
+    assert_eq!(tree.bounds(), Some(([-3.0, -1.0, 0.0], [3.0, 1.0, 0.5])));
+}
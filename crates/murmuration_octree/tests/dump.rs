@@ -0,0 +1,41 @@
+//! Checks the structure dumps (and `Debug`) on empty and populated trees.
+use murmuration_octree::{NonFinitePolicy, Octree};
+
+#[test]
+fn empty_tree() {
+    let tree: Octree<u32, [f32; 3]> = Octree::new();
+    assert_eq!(format!("{tree:?}"), "Octree {\n\n}");
+    assert_eq!(
+        tree.to_dot(),
+        "digraph octree {\n    node [shape=box, fontname=monospace];\n}\n"
+    );
+    assert_eq!(tree.to_json(), r#"{"len":0,"parked":[],"root":null}"#);
+}
+
+#[test]
+fn dumps_are_valid() {
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    for i in 0..50_u32 {
+        let n = i as f32;
+        tree.add(&[n, -n, n * 0.5], format!("item {i}"));
+    }
+    tree.add(&[1.0, -1.0, 0.5], "duplicate".to_string());
+    tree.add(&[f32::INFINITY, 0.0, 0.0], "parked".to_string());
+
+    let json: serde_json::Value = serde_json::from_str(&tree.to_json()).unwrap();
+    assert_eq!(json["len"], 52);
+    assert_eq!(json["parked"][0]["point"][0], "inf");
+    assert!(json["root"]["children"].is_array());
+
+    let dot = tree.to_dot();
+    assert!(dot.contains("item 7"));
+    assert!(dot.contains("parked [label="));
+    // Every node but the root has one edge leading to it
+    let nodes = dot
+        .lines()
+        .filter(|line| {
+            line.starts_with("    n") && line.contains("[label=") && !line.contains("->")
+        })
+        .count();
+    assert_eq!(dot.matches("->").count() + 1, nodes);
+}
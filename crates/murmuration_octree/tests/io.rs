@@ -0,0 +1,112 @@
+//! Checks loading and writing point clouds in each format.
+#![cfg(feature = "io")]
+use murmuration_octree::{Octree, PointCloudError, PointFormat};
+
+fn sorted(mut ids: Vec<usize>) -> Vec<usize> {
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn round_trip() {
+    let points = [
+        [0.0, 0.0, 0.0],
+        [1.5, -2.25, 3.0],
+        [1e-8, 1e8, -0.1],
+        [1.5, -2.25, 3.0],
+    ];
+    let mut tree = Octree::new();
+    for (i, point) in points.iter().enumerate() {
+        tree.add(point, i);
+    }
+
+    for format in [
+        PointFormat::Ply,
+        PointFormat::BinaryPly,
+        PointFormat::Xyz,
+        PointFormat::Csv,
+    ] {
+        let mut file = Vec::new();
+        tree.write_points(&mut file, format).unwrap();
+        let loaded = Octree::load_points(&file[..], format).unwrap();
+        assert_eq!(loaded.len(), points.len(), "{format:?}");
+        for point in points {
+            assert_eq!(
+                loaded.get(&point).count(),
+                tree.get(&point).count(),
+                "{format:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn ply_with_extra_elements() {
+    let file = "ply\n\
+        format ascii 1.0\n\
+        comment made by hand\n\
+        element camera 1\n\
+        property float fov\n\
+        element vertex 3\n\
+        property uchar red\n\
+        property float z\n\
+        property float y\n\
+        property float x\n\
+        element face 1\n\
+        property list uchar int vertex_indices\n\
+        end_header\n\
+        90\n\
+        255 3 2 1\n\
+        0 0 0 0\n\
+        7 -1 -1 -1\n\
+        3 0 1 2\n";
+    let tree =
+        Octree::load_points_with(file.as_bytes(), PointFormat::Ply, |_, values| values[0]).unwrap();
+    assert_eq!(tree.get_single(&[1.0, 2.0, 3.0]), Some(&255.0));
+    assert_eq!(tree.get_single(&[-1.0, -1.0, -1.0]), Some(&7.0));
+}
+
+#[test]
+fn binary_big_endian_ply() {
+    let mut file = b"ply\nformat binary_big_endian 1.0\nelement vertex 2\n\
+        property double x\nproperty double y\nproperty double z\nproperty short id\nend_header\n"
+        .to_vec();
+    for (point, id) in [([1.0_f64, 2.0, 3.0], 10_i16), ([-4.0, 5.0, -6.0], -20)] {
+        for n in point {
+            file.extend_from_slice(&n.to_be_bytes());
+        }
+        file.extend_from_slice(&id.to_be_bytes());
+    }
+    let tree =
+        Octree::load_points_with(&file[..], PointFormat::Ply, |_, values| values[3]).unwrap();
+    assert_eq!(tree.get_single(&[-4.0, 5.0, -6.0]), Some(&-20.0));
+
+    // Cut off part way through the last vertex
+    let err = Octree::load_points(&file[..file.len() - 4], PointFormat::Ply).unwrap_err();
+    assert!(matches!(err, PointCloudError::Io(_)), "{err}");
+}
+
+#[test]
+fn csv_and_xyz() {
+    let csv = "id, Z, Y, X\n1, 3, 2, 1\n\n2, 0, 0, 0\n";
+    let tree = Octree::load_points(csv.as_bytes(), PointFormat::Csv).unwrap();
+    assert_eq!(tree.get_single(&[1.0, 2.0, 3.0]), Some(&0));
+
+    let xyz = "# scan 1\n1 2 3 0.5\n4 5 6 0.25\n";
+    let tree = Octree::load_points(xyz.as_bytes(), PointFormat::Xyz).unwrap();
+    assert_eq!(
+        sorted(tree.within(&[0.0, 0.0, 0.0], 4.0).copied().collect()),
+        [0]
+    );
+
+    let err = Octree::load_points("1 2 3\n1 two 3\n".as_bytes(), PointFormat::Xyz).unwrap_err();
+    assert!(
+        matches!(err, PointCloudError::Parse { line: 2, .. }),
+        "{err}"
+    );
+    let err = Octree::load_points("a,b,c\n1,2,3\n".as_bytes(), PointFormat::Csv).unwrap_err();
+    assert!(
+        matches!(err, PointCloudError::Parse { line: 1, .. }),
+        "{err}"
+    );
+}
@@ -0,0 +1,61 @@
+//! Checks the item budget and fallible adding.
+use murmuration_octree::{AddError, NonFinitePolicy, Octree};
+
+#[test]
+fn try_add_respects_max_items() {
+    let mut tree = Octree::with_capacity(100);
+    tree.set_max_items(Some(50));
+    for i in 0..50_u32 {
+        assert_eq!(tree.try_add(&[i, i % 7, i % 3], i), Ok(()));
+    }
+    assert_eq!(tree.try_add(&[1, 2, 3], 50), Err(AddError::Full(50)));
+    assert_eq!(tree.len(), 50);
+
+    // Removing an item makes room for another
+    assert!(tree.remove(&[0, 0, 0], &0));
+    assert_eq!(tree.try_add(&[1, 2, 3], 50), Ok(()));
+    assert_eq!(
+        tree.try_add(&[1, 2, 3], 51).map_err(AddError::into_data),
+        Err(51)
+    );
+
+    tree.set_max_items(None);
+    assert_eq!(tree.try_add(&[1, 2, 3], 51), Ok(()));
+    assert_eq!(tree.len(), 51);
+    tree.validate().unwrap();
+}
+
+#[test]
+fn parked_items_count_towards_max_items() {
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    tree.set_max_items(Some(2));
+    tree.reserve(2);
+    assert_eq!(tree.try_add(&[f32::NAN, 0.0, 0.0], 1), Ok(()));
+    assert_eq!(tree.try_add(&[1.0, 2.0, 3.0], 2), Ok(()));
+    assert_eq!(
+        tree.try_add(&[f32::NAN, 0.0, 0.0], 3),
+        Err(AddError::Full(3))
+    );
+    assert_eq!(tree.try_add(&[4.0, 5.0, 6.0], 4), Err(AddError::Full(4)));
+}
+
+#[test]
+fn non_finite_is_checked_before_max_items() {
+    let mut tree = Octree::new();
+    tree.set_max_items(Some(0));
+    assert_eq!(
+        tree.try_add(&[f64::INFINITY, 0.0, 0.0], ()),
+        Err(AddError::NonFinite(()))
+    );
+    assert_eq!(tree.try_add(&[1.0, 0.0, 0.0], ()), Err(AddError::Full(())));
+}
+
+#[test]
+#[should_panic(expected = "maximum number of items")]
+fn add_panics_when_full() {
+    let mut tree = Octree::with_bucket_size(4);
+    tree.set_max_items(Some(10));
+    for i in 0..11_i16 {
+        tree.add(&[i, -i, 0], i);
+    }
+}
@@ -0,0 +1,137 @@
+//! Checks Morton codes and iterating in Morton order.
+use murmuration_octree::{NonFinitePolicy, Octree, OrderedBinary, Point, PointData};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[test]
+fn morton_codes_round_trip() {
+    let mut rng = StdRng::seed_from_u64(48);
+    for _ in 0..1000 {
+        let point = [0; 3]
+            .map(|_| rng.gen::<f32>() * 2000.0 - 1000.0)
+            .get_point();
+        assert_eq!(PointData::from_morton(point.morton()), point);
+        let point = [0; 3].map(|_| rng.gen::<u128>()).get_point();
+        assert_eq!(PointData::from_morton(point.morton()), point);
+    }
+}
+
+#[test]
+fn iter_morton_is_sorted() {
+    let mut rng = StdRng::seed_from_u64(480);
+    for bucket_size in [1, 8] {
+        let mut tree = Octree::with_bucket_size(bucket_size);
+        for id in 0..2000_u32 {
+            // The small range gives plenty of duplicates
+            tree.add(&[0; 3].map(|_| rng.gen_range(-10_i32..10)), id);
+        }
+
+        let items = tree.iter_morton().collect::<Vec<_>>();
+        let codes = items
+            .iter()
+            .map(|(point, _)| point.get_point().morton())
+            .collect::<Vec<_>>();
+        assert!(codes.windows(2).all(|pair| pair[0] <= pair[1]));
+        let mut ids = items.iter().map(|(_, id)| **id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert!(ids.into_iter().eq(0..2000));
+    }
+}
+
+#[test]
+fn iter_morton_skips_parked() {
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    tree.add(&[f32::NAN, 0.0, 0.0], 1);
+    tree.add(&[1.0, 2.0, 3.0], 2);
+    assert_eq!(
+        tree.iter_morton().collect::<Vec<_>>(),
+        [([1.0, 2.0, 3.0], &2)]
+    );
+}
+
+/// Checks `in_cell` against filtering every item by its cell, for cells with items in and random
+/// (mostly empty) ones
+fn in_cell_matches_brute_force<N: OrderedBinary + Copy>(seed: u64, coord: impl Fn(&mut StdRng) -> N)
+where
+    N::Ordered: Into<u128>,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    for bucket_size in [1, 4, 16] {
+        let mut tree = Octree::with_bucket_size(bucket_size);
+        let mut items = Vec::new();
+        for id in 0..1000_u32 {
+            // Reuse points sometimes so that buckets hold duplicates
+            let point = if id > 0 && rng.gen_bool(0.1) {
+                items[rng.gen_range(0..items.len())]
+            } else {
+                [coord(&mut rng), coord(&mut rng), coord(&mut rng)]
+            };
+            tree.add(&point, id);
+            items.push(point);
+        }
+        for depth in 0..=<[N; 3]>::MAX_DEPTH {
+            for attempt in 0..10 {
+                let cell = if attempt % 2 == 0 {
+                    items[rng.gen_range(0..items.len())]
+                        .get_point()
+                        .cell_coords(depth)
+                } else {
+                    [coord(&mut rng), coord(&mut rng), coord(&mut rng)]
+                        .get_point()
+                        .cell_coords(depth)
+                };
+                let found = tree.in_cell(depth, cell).collect::<Vec<_>>();
+                // Every item is returned with its own point, in Morton order
+                for (point, id) in &found {
+                    assert!(items[**id as usize].get_point() == point.get_point());
+                }
+                let codes = found
+                    .iter()
+                    .map(|(point, _)| point.get_point().morton().map(Into::into))
+                    .collect::<Vec<[u128; 3]>>();
+                assert!(codes.windows(2).all(|pair| pair[0] <= pair[1]));
+
+                let mut found = found.into_iter().map(|(_, id)| *id).collect::<Vec<_>>();
+                found.sort_unstable();
+                let expected = (0..1000)
+                    .filter(|id| items[*id as usize].get_point().cell_coords(depth) == cell)
+                    .collect::<Vec<_>>();
+                assert_eq!(found, expected, "bucket {bucket_size} depth {depth}");
+            }
+        }
+    }
+}
+
+#[test]
+fn in_cell_i16() {
+    in_cell_matches_brute_force(49, |rng| rng.gen_range(-300_i16..300));
+}
+
+#[test]
+fn in_cell_f32() {
+    in_cell_matches_brute_force(490, |rng| rng.gen_range(-100.0_f32..100.0));
+}
+
+#[test]
+fn in_cell_empty() {
+    let tree = Octree::<u32, [i32; 3]>::with_bucket_size(8);
+    assert_eq!(tree.in_cell(0, [0, 0, 0]).count(), 0);
+    let mut tree = Octree::with_bucket_size(8);
+    tree.add(&[1_i32, 2, 3], 0);
+    assert_eq!(
+        tree.in_cell(32, [1, 2, 3].get_point().cell_coords(32))
+            .count(),
+        1
+    );
+    assert_eq!(
+        tree.in_cell(32, [1, 2, 4].get_point().cell_coords(32))
+            .count(),
+        0
+    );
+    // Only part of the bucket is in the cell
+    tree.add(&[-1, 2, 3], 1);
+    let found = tree
+        .in_cell(1, [1, 1, 1])
+        .map(|(_, id)| *id)
+        .collect::<Vec<_>>();
+    assert_eq!(found, [0]);
+}
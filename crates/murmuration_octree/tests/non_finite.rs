@@ -0,0 +1,87 @@
+//! Checks how points with NaN or infinite coordinates are handled by each `NonFinitePolicy`.
+use murmuration_octree::{AddError, NonFinitePolicy, Octree};
+
+const NON_FINITE: [[f32; 3]; 4] = [
+    [f32::NAN, 0.0, 0.0],
+    [0.0, f32::INFINITY, 0.0],
+    [0.0, 0.0, f32::NEG_INFINITY],
+    [f32::NAN, f32::INFINITY, f32::NEG_INFINITY],
+];
+
+fn sorted<'a>(items: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+    let mut items: Vec<_> = items.copied().collect();
+    items.sort_unstable();
+    items
+}
+
+#[test]
+fn try_add_rejects_non_finite_under_insert() {
+    let mut tree = Octree::new();
+    assert_eq!(tree.non_finite_policy(), NonFinitePolicy::Insert);
+    for (id, point) in (0..).zip(NON_FINITE) {
+        assert_eq!(tree.try_add(&point, id), Err(AddError::NonFinite(id)));
+    }
+    assert!(tree.is_empty());
+    assert_eq!(tree.parked().count(), 0);
+
+    assert_eq!(tree.try_add(&[1.0, 2.0, 3.0], 10), Ok(()));
+    assert_eq!(tree.len(), 1);
+    tree.validate().unwrap();
+
+    // `add` still inserts them under `Insert`
+    tree.add(&[f32::INFINITY, 0.0, 0.0], 11);
+    assert_eq!(sorted(tree.get(&[f32::INFINITY, 0.0, 0.0])), [11]);
+    assert_eq!(tree.parked().count(), 0);
+}
+
+#[test]
+fn park_excludes_non_finite_from_queries() {
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    for (id, point) in (0..).zip(NON_FINITE) {
+        assert_eq!(tree.try_add(&point, id), Ok(()));
+    }
+    tree.add(&[1.0, 2.0, 3.0], 10);
+    tree.add(&[-1.0, 0.0, 0.0], 11);
+    assert_eq!(tree.len(), 6);
+    assert_eq!(sorted(tree.parked()), [0, 1, 2, 3]);
+
+    assert_eq!(
+        sorted(tree.within(&[0.0, 0.0, 0.0], f32::INFINITY)),
+        [10, 11]
+    );
+    for point in NON_FINITE {
+        assert_eq!(tree.get(&point).count(), 0);
+    }
+    tree.validate().unwrap();
+}
+
+#[test]
+fn parked_items_can_be_removed_and_moved() {
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    for (id, point) in (0..).zip(NON_FINITE) {
+        tree.add(&point, id);
+    }
+    tree.add(&[1.0, 2.0, 3.0], 10);
+
+    // Only the matching point and data are removed
+    assert!(!tree.remove(&NON_FINITE[0], &1));
+    assert!(tree.remove(&NON_FINITE[1], &1));
+    assert!(!tree.remove(&NON_FINITE[1], &1));
+    assert_eq!(sorted(tree.parked()), [0, 2, 3]);
+    assert_eq!(tree.len(), 4);
+
+    // Moving to a finite point puts it back in the tree, and moving out parks it again
+    assert!(tree.move_data(&NON_FINITE[0], &[4.0, 5.0, 6.0], 0));
+    assert_eq!(sorted(tree.parked()), [2, 3]);
+    assert_eq!(sorted(tree.get(&[4.0, 5.0, 6.0])), [0]);
+    assert!(tree.move_data(&[1.0, 2.0, 3.0], &NON_FINITE[2], 10));
+    assert_eq!(sorted(tree.parked()), [2, 3, 10]);
+    assert_eq!(tree.get(&[1.0, 2.0, 3.0]).count(), 0);
+
+    for (id, point) in [(2, NON_FINITE[2]), (3, NON_FINITE[3]), (10, NON_FINITE[2])] {
+        assert!(tree.remove(&point, &id));
+    }
+    assert_eq!(tree.parked().count(), 0);
+    assert_eq!(tree.len(), 1);
+    tree.validate().unwrap();
+}
@@ -0,0 +1,85 @@
+//! Checks that floats are converted to an ordered format that sorts them by value.
+use murmuration_octree::{Octree, OrderedBinary};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const F32S: [f32; 13] = [
+    f32::NEG_INFINITY,
+    f32::MIN,
+    -1e10,
+    -2.5,
+    -1.0,
+    -f32::MIN_POSITIVE,
+    -1e-45,
+    -0.0,
+    0.0,
+    1e-45,
+    1.0,
+    f32::MAX,
+    f32::INFINITY,
+];
+
+const F64S: [f64; 13] = [
+    f64::NEG_INFINITY,
+    f64::MIN,
+    -1e300,
+    -2.5,
+    -1.0,
+    -f64::MIN_POSITIVE,
+    -5e-324,
+    -0.0,
+    0.0,
+    5e-324,
+    1.0,
+    f64::MAX,
+    f64::INFINITY,
+];
+
+#[test]
+fn floats_keep_their_order() {
+    for pair in F32S.windows(2) {
+        assert!(pair[0].to_ordered() < pair[1].to_ordered(), "{pair:?}");
+    }
+    for pair in F64S.windows(2) {
+        assert!(pair[0].to_ordered() < pair[1].to_ordered(), "{pair:?}");
+    }
+}
+
+#[test]
+fn floats_round_trip() {
+    for value in F32S.into_iter().chain([f32::NAN, -f32::NAN]) {
+        let back = f32::from_ordered(value.to_ordered());
+        assert_eq!(back.to_bits(), value.to_bits());
+    }
+    for value in F64S.into_iter().chain([f64::NAN, -f64::NAN]) {
+        let back = f64::from_ordered(value.to_ordered());
+        assert_eq!(back.to_bits(), value.to_bits());
+    }
+}
+
+#[test]
+fn negative_queries_match_brute_force() {
+    let mut rng = StdRng::seed_from_u64(31);
+    let points: Vec<[f32; 3]> = (0..500)
+        .map(|_| [(); 3].map(|()| rng.gen_range(-100.0..20.0)))
+        .collect();
+    let mut tree = Octree::new();
+    for (i, point) in points.iter().enumerate() {
+        tree.add(point, i);
+    }
+
+    for _ in 0..100 {
+        let centre = [(); 3].map(|()| rng.gen_range(-110.0..30.0));
+        let distance = rng.gen_range(1.0..40.0);
+        let mut found: Vec<_> = tree.within(&centre, distance).copied().collect();
+        found.sort_unstable();
+        let expected: Vec<_> = (0..points.len())
+            .filter(|&i| {
+                let squared: f32 = (0..3)
+                    .map(|axis| (points[i][axis] - centre[axis]).powi(2))
+                    .sum();
+                squared <= distance * distance
+            })
+            .collect();
+        assert_eq!(found, expected, "within {distance} of {centre:?}");
+    }
+}
@@ -0,0 +1,110 @@
+//! Checks that persistent octree snapshots keep their items while the tree changes.
+use murmuration_octree::PersistentOctree;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+fn sqr_dist(a: &[i32; 3], b: &[i32; 3]) -> i64 {
+    (0..3).map(|i| i64::from(a[i] - b[i]).pow(2)).sum()
+}
+
+fn sorted<'a>(ids: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+    let mut ids = ids.copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids
+}
+
+/// Checks every query on `tree` against a brute force list of its items
+fn check(tree: &PersistentOctree<u32, [i32; 3]>, items: &[([i32; 3], u32)], rng: &mut StdRng) {
+    assert_eq!(tree.len(), items.len());
+    assert_eq!(sorted(tree.iter()), sorted(items.iter().map(|(_, id)| id)));
+
+    let centre = [0; 3].map(|_| rng.gen_range(-40..40));
+    let expected = items.iter().filter(|(point, _)| *point == centre);
+    assert_eq!(
+        sorted(tree.get(&centre)),
+        sorted(expected.map(|(_, id)| id))
+    );
+
+    let radius = rng.gen_range(0..30);
+    let expected = items
+        .iter()
+        .filter(|(point, _)| sqr_dist(point, &centre) <= i64::from(radius).pow(2))
+        .map(|(_, id)| id);
+    let expected = sorted(expected);
+    assert_eq!(sorted(tree.within(&centre, radius)), expected);
+    assert_eq!(tree.count_within(&centre, radius), expected.len());
+
+    let max = centre.map(|n| n + radius);
+    let expected = items
+        .iter()
+        .filter(|(point, _)| (0..3).all(|i| centre[i] <= point[i] && point[i] <= max[i]));
+    assert_eq!(
+        sorted(tree.in_aabb(&centre, &max)),
+        sorted(expected.map(|(_, id)| id))
+    );
+}
+
+#[test]
+fn snapshots_are_unchanged() {
+    let mut rng = StdRng::seed_from_u64(40);
+    let mut tree = PersistentOctree::new();
+    let mut items: Vec<([i32; 3], u32)> = Vec::new();
+    let mut snapshots = Vec::new();
+
+    for step in 0..2000 {
+        let random_point = |rng: &mut StdRng| [0; 3].map(|_| rng.gen_range(-40..40));
+        match rng.gen_range(0..10) {
+            0..=4 => {
+                let point = random_point(&mut rng);
+                tree.add(&point, step);
+                items.push((point, step));
+            }
+            5..=6 if !items.is_empty() => {
+                let (point, id) = items.swap_remove(rng.gen_range(0..items.len()));
+                assert!(tree.remove(&point, &id), "step {step}: failed to remove");
+                assert!(!tree.remove(&point, &id), "step {step}: removed twice");
+            }
+            _ if !items.is_empty() => {
+                let ind = rng.gen_range(0..items.len());
+                let new_point = random_point(&mut rng);
+                let (point, id) = items[ind];
+                assert!(tree.move_data(&point, &new_point, id), "step {step}");
+                items[ind].0 = new_point;
+            }
+            _ => {}
+        }
+        if step % 100 == 0 {
+            snapshots.push((tree.snapshot(), items.clone()));
+        }
+        check(&tree, &items, &mut rng);
+    }
+
+    for (snapshot, items) in &snapshots {
+        check(snapshot, items, &mut rng);
+    }
+}
+
+#[test]
+fn snapshots_share_until_changed() {
+    let mut tree = PersistentOctree::new();
+    for i in 0..100_u32 {
+        tree.add(&[i, i * 2, 7], i);
+    }
+    let snapshot = tree.snapshot();
+    assert!(snapshot.ptr_eq(&tree));
+
+    assert!(!tree.remove(&[5, 10, 7], &6));
+    assert!(snapshot.ptr_eq(&tree));
+
+    assert!(tree.remove(&[5, 10, 7], &5));
+    assert!(!snapshot.ptr_eq(&tree));
+    assert_eq!(snapshot.get_single(&[5, 10, 7]), Some(&5));
+    assert_eq!(tree.get_single(&[5, 10, 7]), None);
+    assert_eq!((snapshot.len(), tree.len()), (100, 99));
+
+    for i in 0..100 {
+        tree.remove(&[i, i * 2, 7], &i);
+    }
+    assert!(tree.is_empty());
+    assert!(tree.ptr_eq(&PersistentOctree::new()));
+    assert_eq!(snapshot.iter().count(), 100);
+}
@@ -0,0 +1,91 @@
+//! Checks that trees survive a round trip through serde and that corrupt input is rejected.
+#![cfg(feature = "serde")]
+use murmuration_octree::{Aggregate, NonFinitePolicy, Octree};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Sums the ids of the items.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Ids(u64);
+
+impl Aggregate<u32, [f32; 3]> for Ids {
+    fn from_item(_point: [f32; 3], id: &u32) -> Self {
+        Ids(u64::from(*id))
+    }
+
+    fn combine(&self, other: &Self) -> Self {
+        Ids(self.0 + other.0)
+    }
+}
+
+fn sorted<'a>(ids: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+    let mut ids: Vec<_> = ids.copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Builds a tree with gaps in its storage by adding lots of items and removing some of them.
+fn churned_tree(bucket_size: usize) -> Octree<u32, [f32; 3], Ids> {
+    let mut rng = StdRng::seed_from_u64(bucket_size as u64);
+    let mut tree = Octree::default();
+    tree.set_bucket_size(bucket_size);
+    tree.set_non_finite_policy(NonFinitePolicy::Park);
+    let mut items = Vec::new();
+    for id in 0..500 {
+        let point = [0; 3].map(|_| rng.gen_range(-10.0..10.0));
+        tree.add(&point, id);
+        items.push((point, id));
+    }
+    for (point, id) in items.iter().step_by(3) {
+        assert!(tree.remove(point, id));
+    }
+    tree.add(&[f32::NAN, 0.0, 0.0], 1000);
+    tree
+}
+
+#[test]
+fn round_trip() {
+    for bucket_size in [1, 8] {
+        let tree = churned_tree(bucket_size);
+        let json = serde_json::to_string(&tree).unwrap();
+        let loaded: Octree<u32, [f32; 3], Ids> = serde_json::from_str(&json).unwrap();
+
+        loaded.validate().unwrap();
+        assert_eq!(loaded.len(), tree.len());
+        assert_eq!(loaded.aggregate(), tree.aggregate());
+        assert_eq!(sorted(loaded.parked()), [1000]);
+        for centre in [[0.0, 0.0, 0.0], [5.0, -5.0, 2.0], [-9.0, 9.0, -9.0]] {
+            assert_eq!(
+                sorted(loaded.within(&centre, 4.0)),
+                sorted(tree.within(&centre, 4.0))
+            );
+        }
+    }
+}
+
+#[test]
+fn rejects_corrupt_trees() {
+    let tree = churned_tree(1);
+    let json = serde_json::to_value(&tree).unwrap();
+
+    // A split with the wrong number of items below it
+    let mut corrupt = json.clone();
+    let split = corrupt["branches"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find_map(|branch| branch[1].get_mut("Split"))
+        .unwrap();
+    split["len"] = (split["len"].as_u64().unwrap() + 1).into();
+    assert!(serde_json::from_value::<Octree<u32, [f32; 3], Ids>>(corrupt).is_err());
+
+    // The root is missing
+    let mut corrupt = json.clone();
+    corrupt["root"] = 1_000_000.into();
+    assert!(serde_json::from_value::<Octree<u32, [f32; 3], Ids>>(corrupt).is_err());
+
+    // A branch is duplicated
+    let mut corrupt = json;
+    let branches = corrupt["branches"].as_array_mut().unwrap();
+    branches.push(branches[0].clone());
+    assert!(serde_json::from_value::<Octree<u32, [f32; 3], Ids>>(corrupt).is_err());
+}
@@ -0,0 +1,140 @@
+//! Checks the statistics about a tree's shape on small trees with known layouts.
+use murmuration_octree::{NonFinitePolicy, Octree, OctreeStats};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[test]
+fn empty_tree() {
+    let tree: Octree<u32, [u8; 3]> = Octree::new();
+    let stats = tree.stats();
+    assert_eq!(
+        stats,
+        OctreeStats {
+            bytes_used: stats.bytes_used,
+            ..OctreeStats::default()
+        }
+    );
+}
+
+#[test]
+fn node_kinds_and_depths() {
+    // One leaf as the root
+    let mut tree = Octree::new();
+    tree.add(&[0_u8, 0, 0], 0);
+    let stats = tree.stats();
+    assert_eq!((stats.splits, stats.skips, stats.leaves), (0, 0, 1));
+    assert_eq!(stats.depth_histogram, [1]);
+    assert_eq!(stats.average_fan_out, 0.0);
+
+    // A skip down to the last bit, which splits into three points (one with three items)
+    let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+    tree.add(&[0_u8, 0, 0], 0);
+    tree.add(&[1, 0, 0], 1);
+    for id in 2..5 {
+        tree.add(&[1, 1, 0], id);
+    }
+    let stats = tree.stats();
+    assert_eq!((stats.items, stats.parked), (5, 0));
+    assert_eq!(
+        (stats.splits, stats.skips, stats.leaves, stats.buckets),
+        (1, 1, 5, 0)
+    );
+    assert_eq!(stats.depth_histogram, [0, 0, 3]);
+    assert_eq!(stats.longest_duplicate_chain, 3);
+    assert_eq!(stats.average_fan_out, 3.0);
+
+    // A full split at the top, with one child skipping down to another split of two
+    let mut tree = Octree::new();
+    for i in 0..8_u8 {
+        tree.add(
+            &[i & 1, (i >> 1) & 1, i >> 2].map(|bit| bit * 128),
+            u32::from(i),
+        );
+    }
+    tree.add(&[0, 0, 1], 8);
+    let stats = tree.stats();
+    assert_eq!((stats.splits, stats.skips, stats.leaves), (2, 1, 9));
+    assert_eq!(stats.depth_histogram, [0, 7, 0, 2]);
+    assert_eq!(stats.longest_duplicate_chain, 1);
+    assert_eq!(stats.average_fan_out, 5.0);
+}
+
+#[test]
+fn duplicates_in_buckets() {
+    let mut tree = Octree::with_bucket_size(8);
+    tree.add(&[2_u8, 1, 0], 0);
+    for id in 1..6 {
+        tree.add(&[1, 1, 0], id);
+    }
+    tree.add(&[2, 1, 0], 6);
+    let stats = tree.stats();
+    assert_eq!((stats.splits, stats.leaves, stats.buckets), (0, 0, 1));
+    assert_eq!(stats.depth_histogram, [1]);
+    assert_eq!(stats.longest_duplicate_chain, 5);
+}
+
+#[test]
+fn large_stacked_bucket() {
+    // Items at one point never split out of their bucket, however many there are
+    let mut tree = Octree::with_bucket_size(8);
+    for id in 0..5000_u32 {
+        tree.add(&[3_i32, -4, 5], id);
+    }
+    for id in 5000..5010 {
+        tree.add(&[3, -4, 6], id);
+    }
+    let stats = tree.stats();
+    assert_eq!(stats.items, 5010);
+    assert_eq!(stats.longest_duplicate_chain, 5000);
+    assert_eq!(stats.leaves, 0);
+}
+
+#[test]
+fn longest_duplicate_chain_matches_brute_force() {
+    let mut rng = StdRng::seed_from_u64(30);
+    for bucket_size in [1, 4, 16] {
+        let mut tree = Octree::with_bucket_size(bucket_size);
+        let mut counts = HashMap::new();
+        for id in 0..500_u32 {
+            let point = [0; 3].map(|_| rng.gen_range(-6_i16..6));
+            tree.add(&point, id);
+            *counts.entry(point).or_insert(0) += 1;
+        }
+        let stats = tree.stats();
+        assert_eq!(
+            stats.longest_duplicate_chain,
+            counts.values().copied().max().unwrap(),
+            "bucket size {bucket_size}"
+        );
+        assert_eq!(stats.items, 500);
+        if bucket_size == 1 {
+            // Every distinct point has its own leaf chain
+            assert_eq!(stats.leaves, 500);
+            assert_eq!(stats.depth_histogram.iter().sum::<usize>(), counts.len());
+        }
+    }
+}
+
+#[test]
+fn fragmentation_after_removals() {
+    let mut tree = Octree::new();
+    let points: Vec<_> = (0..100_u32).map(|i| [i % 10, i / 10, i % 7]).collect();
+    for (id, point) in points.iter().enumerate() {
+        tree.add(point, id);
+    }
+    assert_eq!(tree.stats().fragmentation, 0.0);
+
+    for (id, point) in points.iter().enumerate().step_by(3) {
+        assert!(tree.remove(point, &id));
+    }
+    let stats = tree.stats();
+    assert_eq!(stats.items, 66);
+    assert_eq!(stats.leaves, 66);
+    assert!(stats.fragmentation > 0.0);
+    assert_eq!(stats.fragmentation, tree.fragmentation());
+
+    tree.compact();
+    let compacted = tree.stats();
+    assert_eq!(compacted.fragmentation, 0.0);
+    assert_eq!(compacted.depth_histogram, stats.depth_histogram);
+}
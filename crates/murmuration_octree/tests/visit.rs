@@ -0,0 +1,146 @@
+//! Checks visiting and manually traversing the nodes of random trees.
+use murmuration_octree::{NodeKind, NodeView, Octree, OrderedBinary, VisitAction};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::fmt::Debug;
+
+fn random_tree<N: OrderedBinary + Copy>(
+    seed: u64,
+    bucket_size: usize,
+    coord: impl Fn(&mut StdRng) -> N,
+) -> Octree<u32, [N; 3]> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut tree = Octree::with_bucket_size(bucket_size);
+    let mut points = Vec::new();
+    for id in 0..400 {
+        // Reuse points sometimes so that there are duplicates
+        let point = if !points.is_empty() && rng.gen_bool(0.1) {
+            points[rng.gen_range(0..points.len())]
+        } else {
+            [coord(&mut rng), coord(&mut rng), coord(&mut rng)]
+        };
+        tree.add(&point, id);
+        points.push(point);
+    }
+    tree
+}
+
+fn count_nodes<N: OrderedBinary>(node: &NodeView<'_, u32, [N; 3]>) -> usize {
+    1 + node
+        .children()
+        .map(|child| count_nodes(&child))
+        .sum::<usize>()
+}
+
+/// Returns every point below `node`, checking that each is inside the cell of the nodes above it
+/// and that the lengths match.
+fn check_node<N>(node: &NodeView<'_, u32, [N; 3]>) -> Vec<([N; 3], u32)>
+where
+    N: OrderedBinary + Copy + Debug,
+{
+    let mut points: Vec<_> = node.points().map(|(point, id)| (point, *id)).collect();
+    for child in node.children() {
+        assert_eq!(child.level(), node.level() + 1);
+        points.extend(check_node(&child));
+    }
+    assert_eq!(
+        node.len(),
+        points.len(),
+        "{:?} at {:?}",
+        node.kind(),
+        node.cell()
+    );
+
+    // Cells are in terms of the ordered coordinates, so a float cell's corners may be NaN
+    let (min, max) = node.cell();
+    for (point, id) in &points {
+        for axis in 0..=2 {
+            let coord = point[axis].to_ordered();
+            assert!(
+                min[axis].to_ordered() <= coord && coord <= max[axis].to_ordered(),
+                "{id} at {point:?} isn't in {:?} cell {:?}",
+                node.kind(),
+                (min, max)
+            );
+        }
+    }
+    points
+}
+
+#[test]
+fn cells_contain_their_points() {
+    for bucket_size in [1, 4, 16] {
+        let tree = random_tree(29, bucket_size, |rng| rng.gen_range(-50_i32..50));
+        let mut points = check_node(&tree.root_node().unwrap());
+        points.sort_unstable_by_key(|(_, id)| *id);
+        assert!(points.iter().map(|(_, id)| *id).eq(0..400));
+
+        let tree = random_tree(30, bucket_size, |rng| rng.gen_range(-50.0_f32..50.0));
+        assert_eq!(check_node(&tree.root_node().unwrap()).len(), 400);
+    }
+}
+
+#[test]
+fn visit_matches_traversal() {
+    let tree = random_tree(31, 4, |rng| rng.gen_range(0_u16..1000));
+    let mut visited = 0;
+    let mut items = 0;
+    tree.visit(|node| {
+        visited += 1;
+        if matches!(node.kind(), NodeKind::Leaf | NodeKind::Bucket) {
+            assert_eq!(node.items().count(), node.len());
+            items += node.len();
+        } else {
+            assert_eq!(node.items().count(), 0);
+        }
+        VisitAction::Descend
+    });
+    assert_eq!(visited, count_nodes(&tree.root_node().unwrap()));
+    assert_eq!(items, tree.len());
+}
+
+#[test]
+fn skip_prunes_subtrees() {
+    let tree = random_tree(32, 1, |rng| rng.gen_range(-50_i32..50));
+    let root = tree.root_node().unwrap();
+    let total = count_nodes(&root);
+
+    // Skip the first child of the first split with more than one node below it
+    let mut split = root;
+    while split.kind() != NodeKind::Split {
+        let child = split.children().next().unwrap();
+        split = child;
+    }
+    let pruned = split
+        .children()
+        .find(|child| count_nodes(child) > 1)
+        .unwrap();
+    let (pruned_min, pruned_max) = pruned.cell();
+
+    let mut visited = 0;
+    let mut items = 0;
+    tree.visit(|node| {
+        visited += 1;
+        let (min, max) = node.cell();
+        if node.level() == pruned.level() && (min, max) == (pruned_min, pruned_max) {
+            return VisitAction::Skip;
+        }
+        let inside = (0..=2).all(|i| pruned_min[i] <= min[i] && max[i] <= pruned_max[i]);
+        assert!(!inside, "visited {:?} inside the skipped cell", node.kind());
+        items += node.items().count();
+        VisitAction::Descend
+    });
+    assert_eq!(visited, total - count_nodes(&pruned) + 1);
+    assert_eq!(items, tree.len() - pruned.len());
+
+    // Stopping ends the whole visit
+    let mut visited = 0;
+    tree.visit(|_| {
+        visited += 1;
+        if visited == 3 {
+            VisitAction::Stop
+        } else {
+            VisitAction::Descend
+        }
+    });
+    assert_eq!(visited, 3);
+}
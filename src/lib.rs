@@ -51,11 +51,15 @@ mod manual;
 mod mut_iter;
 mod plugin;
 mod query;
+#[cfg(feature = "serde")]
+mod snapshot;
 mod tree;
 
 pub use manual::{update_spatial_tree, WorldExt};
 pub use plugin::SpatialPlugin;
 pub use query::{SpatialQuery, TransformQuery};
+#[cfg(feature = "serde")]
+pub use snapshot::SpatialSnapshot;
 pub use tree::SpatialTree;
 
 #[doc(hidden)] // This is only public for `SpatialTree::update_tree`
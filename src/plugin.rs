@@ -1,4 +1,4 @@
-use bevy::app::{App, Plugin};
+use bevy::app::{App, Last, Plugin};
 use bevy::ecs::prelude::*;
 use murmuration_octree::{Point, PointData};
 use std::marker::PhantomData;
@@ -35,21 +35,43 @@ mod sealed {
 ///
 /// You can also add a filter component with the `F` generic, in which case this will only add
 /// entities with that component to the [`SpatialTree`].
-pub struct SpatialPlugin<P: Component + Point, F: sealed::OptComponent = sealed::NoFilter>(
-    PhantomData<(P, F)>,
-);
+pub struct SpatialPlugin<P: Component + Point, F: sealed::OptComponent = sealed::NoFilter> {
+    compaction_threshold: Option<f32>,
+    marker: PhantomData<(P, F)>,
+}
 
 impl<P: Component + Point, F: sealed::OptComponent> SpatialPlugin<P, F> {
     /// Create a new `SpatialPlugin<P, F>`
     pub fn new() -> Self {
-        Self(PhantomData)
+        Self {
+            compaction_threshold: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Automatically [compacts](SpatialTree::compact) the tree at the end of each frame where its
+    /// [fragmentation](SpatialTree::fragmentation) is above `threshold` (from `0.0` to `1.0`).
+    ///
+    /// This keeps queries fast when lots of entities are spawned and despawned over time.
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use murmuration::SpatialPlugin;
+    /// App::new().add_plugins((
+    ///     DefaultPlugins,
+    ///     SpatialPlugin::<Transform>::new().with_auto_compaction(0.5),
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn with_auto_compaction(mut self, threshold: f32) -> Self {
+        self.compaction_threshold = Some(threshold);
+        self
     }
 }
 
 impl<P: Component + Point, F: sealed::OptComponent> Default for SpatialPlugin<P, F> {
     /// Create a new `SpatialPlugin<P, F>`
     fn default() -> Self {
-        Self(PhantomData)
+        Self::new()
     }
 }
 
@@ -63,6 +85,14 @@ impl<P: Component + Point, F: sealed::OptComponent> Plugin for SpatialPlugin<P,
         );
         app.init_resource::<SpatialTree<P>>();
 
+        if let Some(threshold) = self.compaction_threshold {
+            app.add_systems(Last, move |mut spatial: ResMut<SpatialTree<P>>| {
+                if spatial.fragmentation() > threshold {
+                    spatial.compact();
+                }
+            });
+        }
+
         // Add an entity to the spatial tree if P gets inserted to it and wasn't already there,
         //  otherwise move the entity to its new position in the tree and update OldPosition.
         // This will also trigger if F is changed (despite it just being a filter) but if we move
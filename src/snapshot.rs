@@ -0,0 +1,62 @@
+use bevy::ecs::{
+    entity::{EntityMapper, MapEntities},
+    prelude::*,
+};
+use murmuration_octree::{
+    NodeKind, NonFinitePolicy, Octree, OrderedBinary, Point, PointData, VisitAction,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::SpatialTree;
+
+/// A copy of the positions of every entity in a [`SpatialTree`] which can be saved and loaded with
+/// serde, returned by [`SpatialTree::snapshot`].
+///
+/// When loading a snapshot into a different world the entities can be remapped with
+/// [`MapEntities`].
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "", transparent)]
+pub struct SpatialSnapshot<P: Point> {
+    tree: Octree<Entity, P>,
+}
+
+impl<P: Point> SpatialSnapshot<P> {
+    /// Returns the saved tree, for querying where entities were when the snapshot was taken.
+    pub fn tree(&self) -> &Octree<Entity, P> {
+        &self.tree
+    }
+
+    /// Returns the saved tree.
+    pub fn into_tree(self) -> Octree<Entity, P> {
+        self.tree
+    }
+}
+
+impl<P: Point> MapEntities for SpatialSnapshot<P> {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.tree
+            .update_data(|entity| *entity = entity_mapper.map_entity(*entity));
+    }
+}
+
+impl<P: Component + Point> SpatialTree<P> {
+    /// Returns a copy of the position of every entity in the tree which can be saved with serde.
+    ///
+    /// Entities with a NaN or infinite position aren't included. As with querying the tree
+    /// directly, this may be out of date unless the tree has been updated first (see
+    /// [`update_tree`](Self::update_tree)).
+    pub fn snapshot(&self) -> SpatialSnapshot<P> {
+        let mut tree = Octree::with_non_finite_policy(NonFinitePolicy::Park);
+        tree.set_bucket_size(self.tree.bucket_size());
+        self.tree.visit(|node| match node.kind() {
+            NodeKind::Leaf | NodeKind::Bucket => {
+                for (point, entity) in node.points() {
+                    tree.add_internal(PointData(point.map(|n| n.to_ordered())), *entity);
+                }
+                VisitAction::Skip
+            }
+            NodeKind::Split | NodeKind::Skip => VisitAction::Descend,
+        });
+        SpatialSnapshot { tree }
+    }
+}
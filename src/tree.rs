@@ -1,5 +1,6 @@
-use bevy::ecs::prelude::*;
-use murmuration_octree::{Octree, Point, PointData};
+use bevy::ecs::{entity::EntityHashSet, prelude::*};
+use bevy::log::warn;
+use murmuration_octree::{NonFinitePolicy, Octree, OctreeStats, Point, PointData};
 
 use crate::{ecs_utils::into_query::IntoQuery, plugin::OldPosition};
 
@@ -12,22 +13,35 @@ use crate::{ecs_utils::into_query::IntoQuery, plugin::OldPosition};
 /// relevant changes, it will only update those relevant to its exact query, so you may need to
 /// update the tree with accurate information before using this to query it, either with
 /// [`SpatialTree::update_tree`] or [`World::update_tree`](crate::WorldExt::update_tree).
+///
+/// Entities whose position has a NaN or infinite coordinate are kept out of all spatial queries
+/// (with a warning logged once) until they have a finite position again.
 #[derive(Resource)]
-pub struct SpatialTree<P: Component + Point>(Octree<Entity, P>);
+pub struct SpatialTree<P: Component + Point> {
+    pub(crate) tree: Octree<Entity, P>,
+    non_finite: EntityHashSet,
+}
 
 impl<P: Component + Point> Default for SpatialTree<P> {
     fn default() -> Self {
-        Self(Octree::default())
+        Self {
+            tree: Octree::with_non_finite_policy(NonFinitePolicy::Park),
+            non_finite: EntityHashSet::default(),
+        }
     }
 }
 
 impl<P: Component + Point> SpatialTree<P> {
     pub(crate) fn add(&mut self, entity: Entity, point: &P) {
-        self.0.add(point, entity);
+        let point = point.get_point();
+        let finite = point.is_finite();
+        self.tree.add_internal(point, entity);
+        self.check_finite(entity, finite);
     }
 
     pub(crate) fn remove(&mut self, entity: Entity, point: &P) -> bool {
-        self.0.remove(point, &entity)
+        self.non_finite.remove(&entity);
+        self.tree.remove(point, &entity)
     }
 
     pub(crate) fn move_entity(
@@ -36,7 +50,26 @@ impl<P: Component + Point> SpatialTree<P> {
         old_point: &PointData<P>,
         new_point: PointData<P>,
     ) -> bool {
-        self.0.move_data_internal(old_point, new_point, entity)
+        let finite = new_point.is_finite();
+        let moved = self.tree.move_data_internal(old_point, new_point, entity);
+        if moved {
+            self.check_finite(entity, finite);
+        }
+        moved
+    }
+
+    /// Warns (once per entity) when an entity has been given a non-finite position.
+    fn check_finite(&mut self, entity: Entity, finite: bool) {
+        if finite {
+            if !self.non_finite.is_empty() {
+                self.non_finite.remove(&entity);
+            }
+        } else if self.non_finite.insert(entity) {
+            warn!(
+                "{entity:?} has a NaN or infinite position so it will be excluded from spatial \
+                queries until it is finite again"
+            );
+        }
     }
 
     /// Returns the entity at the given point or `None` if there is nothing there.
@@ -74,7 +107,7 @@ impl<P: Component + Point> SpatialTree<P> {
     /// }
     /// ```
     pub fn get(&self, point: &P) -> impl Iterator<Item = Entity> + '_ {
-        self.0.get(point).copied()
+        self.tree.get(point).copied()
     }
 
     /// Returns all the entities within a radius `distance` of the given `point`.
@@ -91,7 +124,122 @@ impl<P: Component + Point> SpatialTree<P> {
     /// }
     /// ```
     pub fn within(&self, point: &P, distance: P::Data) -> impl Iterator<Item = Entity> + '_ {
-        self.0.within(point, distance).copied()
+        self.tree.within(point, distance).copied()
+    }
+
+    /// Returns all the entities inside the ellipsoid around `point` with the given radius along
+    /// each axis.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use murmuration::SpatialTree;
+    /// /// Prints all the entities within 20 horizontally and 3 vertically of (0, 0, 0)
+    /// fn aggro_system(tree: Res<SpatialTree<Transform>>) {
+    ///     for entity in tree.within_scaled(&Transform::from_xyz(0.0, 0.0, 0.0), [20.0, 3.0, 20.0]) {
+    ///         println!("{:?}", entity);
+    ///     }
+    /// }
+    /// ```
+    pub fn within_scaled(
+        &self,
+        point: &P,
+        radii: [P::Data; 3],
+    ) -> impl Iterator<Item = Entity> + '_ {
+        self.tree.within_scaled(point, radii).copied()
+    }
+
+    /// Returns the number of entities within a radius `distance` of the given `point`.
+    ///
+    /// This is much faster than counting [`within`](Self::within) for large or dense areas.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use murmuration::SpatialTree;
+    /// /// Prints whether there is a crowd near (0, 0, 0)
+    /// fn crowd_system(tree: Res<SpatialTree<Transform>>) {
+    ///     if tree.count_within(&Transform::from_xyz(0.0, 0.0, 0.0), 10.0) > 20 {
+    ///         println!("It's getting crowded");
+    ///     }
+    /// }
+    /// ```
+    pub fn count_within(&self, point: &P, distance: P::Data) -> usize {
+        self.tree.count_within(point, distance)
+    }
+
+    /// Returns the number of entities between `min` and `max` (inclusive) on every axis.
+    pub fn count_in_aabb(&self, min: &P, max: &P) -> usize {
+        self.tree.count_in_aabb(min, max)
+    }
+
+    /// Returns the smallest and largest corners (inclusive) of the box around every entity in the
+    /// tree, or `None` if it is empty.
+    ///
+    /// This isn't cached and can look at every entity (see
+    /// [`Octree::bounds`]), so it suits one-off uses such as
+    /// saving a level rather than being run every frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use murmuration::SpatialTree;
+    /// /// Returns the size of the area the entities are spread over
+    /// fn world_size(tree: &SpatialTree<Transform>) -> Option<Vec3> {
+    ///     let (min, max) = tree.bounds()?;
+    ///     Some(Vec3::from(max) - Vec3::from(min))
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn bounds(&self) -> Option<([P::Data; 3], [P::Data; 3])> {
+        self.tree.bounds()
+    }
+
+    /// Returns the number of entities in the tree.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns `true` if there are no entities in the tree.
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Returns statistics about the shape and memory use of the underlying tree.
+    ///
+    /// This has to look at the whole tree so is fairly slow, but can be useful for diagnosing
+    /// performance problems such as lots of entities stacked at the same point.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use murmuration::SpatialTree;
+    /// fn log_tree_stats(tree: Res<SpatialTree<Transform>>) {
+    ///     info!("Spatial tree stats:\n{}", tree.stats());
+    /// }
+    /// ```
+    pub fn stats(&self) -> OctreeStats {
+        self.tree.stats()
+    }
+
+    /// Returns the fraction (from `0.0` to `1.0`) of the tree's storage which is left empty by
+    /// removed entities, see [`compact`](Self::compact).
+    pub fn fragmentation(&self) -> f32 {
+        self.tree.fragmentation()
+    }
+
+    /// Rearranges the tree's storage to remove any gaps left by removed entities and keep nearby
+    /// entities close together in memory, which speeds up queries.
+    ///
+    /// This can be done automatically with
+    /// [`SpatialPlugin::with_auto_compaction`](crate::SpatialPlugin::with_auto_compaction).
+    pub fn compact(&mut self) {
+        self.tree.compact();
+    }
+
+    /// Like [`compact`](Self::compact), but also frees any storage that isn't currently in use.
+    pub fn shrink_to_fit(&mut self) {
+        self.tree.shrink_to_fit();
     }
 
     /// Updates the spatial tree with any changes to the entities passed in.
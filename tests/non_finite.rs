@@ -0,0 +1,82 @@
+//! Checks that entities with non-finite positions are kept out of the tree with one warning each.
+use bevy::log::{
+    tracing_subscriber::{layer::Context, prelude::*, Layer, Registry},
+    Level,
+};
+use bevy::prelude::*;
+use bevy::utils::tracing::{subscriber, Event, Subscriber};
+use murmuration::{SpatialPlugin, SpatialTree};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Counts the warnings logged while it is the subscriber.
+struct CountWarnings(Arc<AtomicUsize>);
+
+impl<S: Subscriber> Layer<S> for CountWarnings {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() == Level::WARN {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn tree_entities(app: &App) -> Vec<Entity> {
+    let tree = app.world().resource::<SpatialTree<Transform>>();
+    tree.within(&Transform::default(), f32::INFINITY).collect()
+}
+
+#[test]
+fn warns_once_per_entity() {
+    let warnings = Arc::new(AtomicUsize::new(0));
+    let layer = CountWarnings(warnings.clone());
+    let count = || warnings.load(Ordering::Relaxed);
+
+    // Observers run as the components are inserted, so everything is logged on this thread
+    subscriber::with_default(Registry::default().with(layer), || {
+        let mut app = App::new();
+        app.add_plugins(SpatialPlugin::<Transform>::new());
+        let world = app.world_mut();
+
+        let finite = world.spawn(Transform::from_xyz(1.0, 2.0, 3.0)).id();
+        let a = world.spawn(Transform::from_xyz(f32::NAN, 0.0, 0.0)).id();
+        assert_eq!(count(), 1);
+        world.flush();
+
+        // Moving between non-finite positions doesn't warn again
+        world
+            .entity_mut(a)
+            .insert(Transform::from_xyz(0.0, f32::INFINITY, 0.0));
+        world
+            .entity_mut(a)
+            .insert(Transform::from_xyz(f32::NAN, f32::NAN, 0.0));
+        assert_eq!(count(), 1);
+
+        let b = world
+            .spawn(Transform::from_xyz(0.0, 0.0, f32::NEG_INFINITY))
+            .id();
+        world.flush();
+        assert_eq!(count(), 2);
+        assert_eq!(tree_entities(&app), [finite]);
+
+        // Becoming finite puts it back in queries, and it warns again if it becomes non-finite
+        let world = app.world_mut();
+        world
+            .entity_mut(a)
+            .insert(Transform::from_xyz(4.0, 5.0, 6.0));
+        let mut entities = tree_entities(&app);
+        entities.sort_unstable();
+        assert_eq!(entities, [finite, a]);
+        let world = app.world_mut();
+        world
+            .entity_mut(a)
+            .insert(Transform::from_xyz(f32::NAN, 0.0, 0.0));
+        assert_eq!(count(), 3);
+
+        // Despawning forgets about the entity
+        world.despawn(b);
+        world.flush();
+        assert_eq!(tree_entities(&app), [finite]);
+    });
+}